@@ -1,14 +1,41 @@
 use amethyst::{
     assets::{AssetStorage, Loader, ProgressCounter},
-    audio::{output::Output, OggFormat, Source, SourceHandle},
-    ecs::{World, WorldExt},
+    audio::{output::Output, AudioSink, OggFormat, Source, SourceHandle},
+    ecs::{Read, ReadExpect, World, WorldExt},
 };
+use log::error;
+
+// Tracks the game plays, relative to the assets directory
+pub const MUSIC_TRACK: &str = "audio/blippy-trance.ogg";
+pub const INTRO_BUZZ_TRACK: &str = "audio/logo_buzz.ogg";
 
 pub struct Sounds {
     pub music: SourceHandle,
     pub intro_buzz: SourceHandle,
 }
 
+/// Audio preferences, changed through the settings menu.
+#[derive(Clone, Copy, Debug)]
+pub struct AudioSettings {
+    pub music: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings { music: true }
+    }
+}
+
+impl AudioSettings {
+    fn music_volume(&self) -> f32 {
+        if self.music {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
 /// Loads an ogg audio track.
 fn load_audio_track(
     loader: &Loader,
@@ -22,25 +49,63 @@ fn load_audio_track(
 /// Initialise audio in the world. This will eventually include
 /// the background tracks as well as the sound effects, but for now
 /// we'll just work on sound effects.
+///
+/// Audio is optional: anything that fails to load is simply not played, so its progress should
+/// be tracked apart from the assets the game can't do without.
 pub fn initialise_audio(world: &mut World, progress_counter: &mut ProgressCounter) {
     let sound_effects = {
         let loader = world.read_resource::<Loader>();
         Sounds {
-            music: load_audio_track(&loader, &world, progress_counter, "audio/blippy-trance.ogg"),
-            intro_buzz: load_audio_track(&loader, &world, progress_counter, "audio/logo_buzz.ogg"),
+            music: load_audio_track(&loader, &world, progress_counter, MUSIC_TRACK),
+            intro_buzz: load_audio_track(&loader, &world, progress_counter, INTRO_BUZZ_TRACK),
         }
     };
 
     // Add sound effects to the world. We have to do this in another scope because
     // world won't let us insert new resources as long as `Loader` is borrowed.
     world.insert(sound_effects);
+    world.insert(AudioSettings::default());
 }
 
-pub fn play_music(sounds: &Sounds, storage: &AssetStorage<Source>, output: Option<&Output>) {
-    if let Some(ref output) = output.as_ref() {
-        if let Some(sound) = storage.get(&sounds.music) {
-            output.play_n_times(sound, 1.0, 20);
-        }
+/// Starts the music on its own sink, so that it can be muted and stopped while playing.
+pub fn start_music(world: &mut World) {
+    let sink = world.exec(
+        #[allow(clippy::type_complexity)]
+        |(sounds, storage, audio_output, settings): (
+            ReadExpect<'_, Sounds>,
+            Read<'_, AssetStorage<Source>>,
+            Option<Read<'_, Output>>,
+            Read<'_, AudioSettings>,
+        )| {
+            let output = audio_output?;
+            let sound = storage.get(&sounds.music)?;
+            let mut sink = AudioSink::new(&output);
+            sink.set_volume(settings.music_volume());
+            for _ in 0..20 {
+                if let Err(e) = sink.append(sound) {
+                    error!("Failed to play music: {}", e);
+                    return None;
+                }
+            }
+            Some(sink)
+        },
+    );
+    if let Some(sink) = sink {
+        world.insert(sink);
+    }
+}
+
+pub fn stop_music(world: &mut World) {
+    if let Some(sink) = world.remove::<AudioSink>() {
+        sink.stop();
+    }
+}
+
+/// Applies changed settings to anything currently playing.
+pub fn apply_settings(world: &mut World) {
+    let settings = *world.read_resource::<AudioSettings>();
+    if let Some(mut sink) = world.try_fetch_mut::<AudioSink>() {
+        sink.set_volume(settings.music_volume());
     }
 }
 
@@ -0,0 +1,466 @@
+use crate::{
+    bot::BotSettings,
+    headless::{self, Report, Scenario},
+    prefabs::{BulletPrefab, EnemyPrefab},
+    resources::{BulletPrefabSet, EnemyPrefabSet, GameplayConfig, Wave},
+    simulation::RunOutcome,
+};
+use amethyst::{
+    assets::{AssetStorage, Prefab},
+    config::Config,
+    ecs::{World, WorldExt},
+    utils::application_root_dir,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum Parameter {
+    EnemyHealth,
+    BulletDamage,
+    WaveDelay,
+    SpawnInterval,
+    TowerCost,
+}
+
+/// Factors to scale the game's values by, on top of what the prefabs and waves say. All of them
+/// are 1 by default, which leaves everything as it is.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tuning {
+    enemy_health: f32,
+    bullet_damage: f32,
+    wave_delay: f32,
+    spawn_interval: f32,
+    tower_cost: f32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning {
+            enemy_health: 1.,
+            bullet_damage: 1.,
+            wave_delay: 1.,
+            spawn_interval: 1.,
+            tower_cost: 1.,
+        }
+    }
+}
+
+impl Tuning {
+    fn factor_mut(&mut self, parameter: Parameter) -> &mut f32 {
+        match parameter {
+            Parameter::EnemyHealth => &mut self.enemy_health,
+            Parameter::BulletDamage => &mut self.bullet_damage,
+            Parameter::WaveDelay => &mut self.wave_delay,
+            Parameter::SpawnInterval => &mut self.spawn_interval,
+            Parameter::TowerCost => &mut self.tower_cost,
+        }
+    }
+
+    // The default spawn interval is scaled here, for the groups without one of their own
+    pub fn apply_to_config(&self, gameplay: &mut GameplayConfig) {
+        gameplay.tower_cost = (gameplay.tower_cost as f32 * self.tower_cost).round() as u32;
+        gameplay.spawn_interval *= self.spawn_interval;
+    }
+
+    // Changes the loaded prefabs themselves, so only entities created afterwards are affected
+    pub fn apply_to_prefabs(&self, world: &mut World) {
+        {
+            let enemy_prefab_set = world.read_resource::<EnemyPrefabSet>();
+            let mut prefabs = world.write_resource::<AssetStorage<Prefab<EnemyPrefab>>>();
+            for handle in enemy_prefab_set.handles() {
+                let prefab = match prefabs.get_mut(handle) {
+                    Some(p) => p,
+                    None => continue,
+                };
+                for index in 0..prefab.len() {
+                    if let Some(data) = prefab.entity(index).and_then(|e| e.data_mut()) {
+                        let hitable = data.hitable_mut();
+                        hitable.health *= self.enemy_health;
+                        hitable.max_health *= self.enemy_health;
+                    }
+                }
+            }
+        }
+
+        let bullet_prefab_set = world.read_resource::<BulletPrefabSet>();
+        let mut prefabs = world.write_resource::<AssetStorage<Prefab<BulletPrefab>>>();
+        for handle in bullet_prefab_set.handles() {
+            let prefab = match prefabs.get_mut(handle) {
+                Some(p) => p,
+                None => continue,
+            };
+            for index in 0..prefab.len() {
+                if let Some(data) = prefab.entity(index).and_then(|e| e.data_mut()) {
+                    data.bullet_mut().hitpoints *= self.bullet_damage;
+                }
+            }
+        }
+    }
+
+    // Groups that go by the default spawn interval are left as they are, see `apply_to_config`
+    pub fn apply_to_waves(&self, waves: &[Wave]) -> Vec<Wave> {
+        waves
+            .iter()
+            .cloned()
+            .map(|mut wave| {
+                wave.delay *= self.wave_delay;
+                for group in wave.groups.iter_mut() {
+                    if let Some(ref mut interval) = group.interval {
+                        *interval *= self.spawn_interval;
+                    }
+                }
+                wave
+            })
+            .collect()
+    }
+
+    // Fields of the gameplay config that `apply_to_config` scales, by how much
+    fn config_fields(&self) -> [(&'static str, f32); 2] {
+        [
+            ("tower_cost", self.tower_cost),
+            ("spawn_interval", self.spawn_interval),
+        ]
+    }
+
+    // Same for the waves and `apply_to_waves`
+    fn wave_fields(&self) -> [(&'static str, f32); 2] {
+        [
+            ("delay", self.wave_delay),
+            ("interval", self.spawn_interval),
+        ]
+    }
+}
+
+/// What to balance, and what for. Read from a RON file, see scenarios/balance_level1.ron.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct BalanceConfig {
+    // Scenario to base every run on. Relative to the config file
+    scenario: PathBuf,
+    // The reference bots. Each of them plays the scenario once per tuning tried, so a spread of
+    // skill levels is what makes losing a matter of percentages
+    bots: Vec<BotSettings>,
+    targets: Vec<Target>,
+    // Range each parameter is searched in, as (min, max) factors. Anything left out stays at 1
+    parameters: BTreeMap<Parameter, (f32, f32)>,
+    // Values tried per parameter, evenly spread over its range
+    #[serde(default = "default_steps")]
+    steps: u32,
+    // Times to go over all the parameters, as the best value for one depends on the others
+    #[serde(default = "default_passes")]
+    passes: u32,
+}
+
+fn default_steps() -> u32 {
+    5
+}
+
+fn default_passes() -> u32 {
+    2
+}
+
+// The share of runs that should be lost by the time the wave is over, e.g. wave 5 with 0.3 means
+// 30% of the bots should lose in one of the first five waves
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct Target {
+    wave: usize,
+    loss_rate: f32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Evaluation {
+    tuning: Tuning,
+    // Sum of the squared differences from the target loss rates
+    error: f32,
+    loss_rates: Vec<Target>,
+}
+
+/// Searches for the tuning that gets the bots in the config closest to its difficulty targets,
+/// and prints it. With `write`, the tuned values also replace the ones in the prefab and level
+/// files, so that the game plays with them from then on.
+pub fn run(config_path: &Path, write: bool) -> anyhow::Result<()> {
+    let config = BalanceConfig::load(config_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read balance config {:?}: {}", config_path, e))?;
+    anyhow::ensure!(!config.bots.is_empty(), "Balance config has no bots");
+    anyhow::ensure!(config.steps >= 2, "Balance config needs at least 2 steps");
+    for (parameter, (min, max)) in &config.parameters {
+        anyhow::ensure!(
+            *min > 0. && min <= max,
+            "Range of {:?} has to be positive, and go from min to max",
+            parameter
+        );
+    }
+    let scenario_path = config_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(&config.scenario);
+    let scenario = headless::load_scenario(&scenario_path)?;
+
+    // Start from the values as they are, or as close as the ranges allow
+    let mut start = Tuning::default();
+    for (parameter, (min, max)) in &config.parameters {
+        let factor = start.factor_mut(*parameter);
+        *factor = factor.clamp(*min, *max);
+    }
+    let mut best = evaluate(&config, &scenario, &scenario_path, &start)?;
+    for _ in 0..config.passes {
+        for (parameter, (min, max)) in &config.parameters {
+            for step in 0..config.steps {
+                let mut tuning = best.tuning.clone();
+                *tuning.factor_mut(*parameter) =
+                    min + (max - min) * step as f32 / (config.steps - 1) as f32;
+                if tuning == best.tuning {
+                    continue;
+                }
+                let evaluation = evaluate(&config, &scenario, &scenario_path, &tuning)?;
+                if evaluation.error < best.error {
+                    best = evaluation;
+                }
+            }
+        }
+    }
+    println!("{}", serde_json::to_string_pretty(&best)?);
+
+    if write {
+        write_tuning(&best.tuning, &scenario.waves_file(&scenario_path)?)?;
+    }
+    Ok(())
+}
+
+// Plays the scenario with every bot, all at once, and compares how often they lose to the targets
+fn evaluate(
+    config: &BalanceConfig,
+    scenario: &Scenario,
+    scenario_path: &Path,
+    tuning: &Tuning,
+) -> anyhow::Result<Evaluation> {
+    let reports = thread::scope(|scope| {
+        let runs: Vec<_> = config
+            .bots
+            .iter()
+            .map(|bot| {
+                let scenario = scenario.clone().with_bot(bot.clone(), tuning.clone());
+                scope.spawn(move || headless::run_loaded(&scenario, scenario_path))
+            })
+            .collect();
+        runs.into_iter()
+            .map(|run| run.join().expect("Simulation panicked"))
+            .collect::<anyhow::Result<Vec<Report>>>()
+    })?;
+
+    let loss_rates: Vec<Target> = config
+        .targets
+        .iter()
+        .map(|target| {
+            let lost = reports
+                .iter()
+                .filter(|r| {
+                    r.outcome == Some(RunOutcome::Defeat) && r.summary.waves_survived < target.wave
+                })
+                .count();
+            Target {
+                wave: target.wave,
+                loss_rate: lost as f32 / reports.len() as f32,
+            }
+        })
+        .collect();
+    let error = config
+        .targets
+        .iter()
+        .zip(&loss_rates)
+        .map(|(target, rate)| (rate.loss_rate - target.loss_rate).powi(2))
+        .sum();
+    Ok(Evaluation {
+        tuning: tuning.clone(),
+        error,
+        loss_rates,
+    })
+}
+
+fn write_tuning(tuning: &Tuning, waves_path: &Path) -> anyhow::Result<()> {
+    let root = application_root_dir()?;
+    let assets = root.join("assets");
+    for path in EnemyPrefabSet::prefab_paths() {
+        scale_file(
+            &assets.join(path),
+            &[
+                ("health", tuning.enemy_health),
+                ("max_health", tuning.enemy_health),
+            ],
+        )?;
+    }
+    for path in BulletPrefabSet::prefab_paths() {
+        scale_file(&assets.join(path), &[("hitpoints", tuning.bullet_damage)])?;
+    }
+    scale_file(
+        &root.join("config").join("gameplay.ron"),
+        &tuning.config_fields(),
+    )?;
+    scale_file(waves_path, &tuning.wave_fields())
+}
+
+fn scale_file(path: &Path, fields: &[(&str, f32)]) -> anyhow::Result<()> {
+    let original = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {:?}: {}", path, e))?;
+    let mut text = original.clone();
+    for (field, factor) in fields {
+        text = scale_field(&text, field, *factor);
+    }
+    if text == original {
+        return Ok(());
+    }
+    fs::write(path, text).map_err(|e| anyhow::anyhow!("Failed to write {:?}: {}", path, e))?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+// Multiplies every number given for the field in RON text, e.g. all the `health: 100.0` in a
+// prefab. Works on the text rather than the parsed file so that comments and layout are kept.
+// Comments and strings are left alone, and only the field's own values are touched, not other
+// fields whose names contain it
+fn scale_field(text: &str, field: &str, factor: f32) -> String {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let token_len = if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if rest.starts_with("/*") {
+            rest.find("*/").map_or(rest.len(), |end| end + 2)
+        } else if c == '"' {
+            string_len(rest)
+        } else if is_ident(c) {
+            rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len())
+        } else {
+            c.len_utf8()
+        };
+        let (token, after) = rest.split_at(token_len);
+        result.push_str(token);
+        rest = after;
+
+        if token != field {
+            continue;
+        }
+        // Only a number right after the colon is the field's value
+        let value = after
+            .trim_start()
+            .strip_prefix(':')
+            .map(str::trim_start)
+            .and_then(|value| {
+                let end = value
+                    .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+                    .unwrap_or(value.len());
+                let (number, after_number) = value.split_at(end);
+                if after_number.starts_with(is_ident) {
+                    return None;
+                }
+                number.parse::<f32>().ok().map(|n| (value, number, n))
+            });
+        if let Some((value, number, n)) = value {
+            result.push_str(&after[..after.len() - value.len()]);
+            // Whole numbers stay whole, RON won't read a float into an integer field
+            if number.contains('.') {
+                result.push_str(&format_number(n * factor));
+            } else {
+                result.push_str(&((n * factor).round() as i64).to_string());
+            }
+            rest = &value[number.len()..];
+        }
+    }
+    result
+}
+
+// Length of the string literal at the start of the text, quotes included
+fn string_len(text: &str) -> usize {
+    let mut escaped = false;
+    for (index, c) in text.char_indices().skip(1) {
+        match c {
+            '"' if !escaped => return index + 1,
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    text.len()
+}
+
+// Rounds to two decimals, and always keeps a decimal point as RON wants one for floats
+fn format_number(value: f32) -> String {
+    let rounded = (value * 100.).round() / 100.;
+    if rounded.fract() == 0. {
+        format!("{:.1}", rounded)
+    } else {
+        rounded.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources;
+    use std::env;
+
+    #[test]
+    fn written_tunings_play_like_the_evaluated_ones() {
+        let tuning = Tuning {
+            wave_delay: 1.5,
+            spawn_interval: 0.5,
+            tower_cost: 2.,
+            ..Default::default()
+        };
+        let root = application_root_dir().unwrap();
+        let level_path = root.join("assets").join("levels").join("level1.ron");
+        let config_path = root.join("config").join("gameplay.ron");
+        let dir = env::temp_dir().join("towpow_written_tunings");
+        fs::create_dir_all(&dir).unwrap();
+        let written_level_path = dir.join("level1.ron");
+        let written_config_path = dir.join("gameplay.ron");
+        fs::copy(&level_path, &written_level_path).unwrap();
+        fs::copy(&config_path, &written_config_path).unwrap();
+
+        scale_file(&written_level_path, &tuning.wave_fields()).unwrap();
+        scale_file(&written_config_path, &tuning.config_fields()).unwrap();
+
+        let level = resources::load_level(&level_path).unwrap();
+        let written_level = resources::load_level(&written_level_path).unwrap();
+        assert_eq!(
+            format!("{:?}", written_level.waves.waves()),
+            format!("{:?}", tuning.apply_to_waves(level.waves.waves()))
+        );
+        let mut config = resources::load_gameplay_config(&config_path).unwrap();
+        tuning.apply_to_config(&mut config);
+        assert_eq!(
+            resources::load_gameplay_config(&written_config_path).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn scales_only_the_field() {
+        let text = "(\n    health: 100.0,\n    max_health: 100.0,\n    healthy: 3.0,\n)";
+        assert_eq!(
+            scale_field(text, "health", 1.255),
+            "(\n    health: 125.5,\n    max_health: 100.0,\n    healthy: 3.0,\n)"
+        );
+        assert_eq!(
+            scale_field("delay:2.0, delay: 3", "delay", 2.),
+            "delay:4.0, delay: 6"
+        );
+    }
+
+    #[test]
+    fn leaves_comments_and_strings_alone() {
+        let text = "(\n    // health: 100.0\n    id: \"health: 5\\\" health: 5\",\n    /* health: 1 */ health: 10.0,\n)";
+        assert_eq!(
+            scale_field(text, "health", 2.),
+            "(\n    // health: 100.0\n    id: \"health: 5\\\" health: 5\",\n    /* health: 1 */ health: 20.0,\n)"
+        );
+    }
+}
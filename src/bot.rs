@@ -0,0 +1,163 @@
+use crate::{
+    components::{ControlledCharacter, Destination, Tower, TowerKind},
+    input::TickInput,
+    resources::{Buildable, Economy, GameplayConfig, LevelMap, TowerType},
+};
+use amethyst::{
+    core::Transform,
+    ecs::{Join, World, WorldExt},
+};
+use nalgebra::{Point2, Vector2};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// How close the bot walks up to a tower before trying to get in. Well within what
+// `PlayerControlSystem` allows
+const ENTER_DISTANCE: f32 = 20.;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BotSettings {
+    // How fast the bot clicks, as no human fires every other tick
+    pub shots_per_second: f32,
+}
+
+impl Default for BotSettings {
+    fn default() -> Self {
+        BotSettings {
+            shots_per_second: 4.,
+        }
+    }
+}
+
+/// Plays the game through the same input a player would give. Its strategy is as simple as it
+/// gets: build a tower halfway down the path, walk into it, and shoot whatever enemy is closest.
+pub struct Bot {
+    settings: BotSettings,
+    // Input already decided on for the next ticks, e.g. releasing a button that was just pressed
+    queued: VecDeque<TickInput>,
+    // Seconds until the bot can fire again
+    reload: f32,
+}
+
+impl Bot {
+    pub fn new(settings: BotSettings) -> Self {
+        Bot {
+            settings,
+            queued: VecDeque::new(),
+            reload: 0.,
+        }
+    }
+
+    // Decides on the input for the next tick of the run in the world
+    pub fn next_input(&mut self, world: &World, step: f32) -> TickInput {
+        self.reload -= step;
+        if let Some(input) = self.queued.pop_front() {
+            return input;
+        }
+
+        let transforms = world.read_storage::<Transform>();
+        let position = |transform: &Transform| {
+            Point2::new(transform.translation().x, transform.translation().y)
+        };
+        let player = (&world.read_storage::<ControlledCharacter>(), &transforms)
+            .join()
+            .next()
+            .map(|(controlled_character, transform)| {
+                (position(transform), controlled_character.locked)
+            });
+        let (player_position, locked) = match player {
+            Some(p) => p,
+            None => return TickInput::default(),
+        };
+
+        if locked {
+            let target = (&world.read_storage::<Destination>(), &transforms)
+                .join()
+                .map(|(_, transform)| position(transform))
+                .min_by(|a, b| {
+                    let a_dist = (a - player_position).norm();
+                    let b_dist = (b - player_position).norm();
+                    a_dist.partial_cmp(&b_dist).expect("Tried to compare a NaN")
+                });
+            let aim = TickInput {
+                mouse: target,
+                ..Default::default()
+            };
+            // Shots go off when the button is released
+            if target.is_some() && self.reload <= 0. {
+                self.reload = 1. / self.settings.shots_per_second;
+                self.queued.push_back(aim.clone());
+                return TickInput { fire: true, ..aim };
+            }
+            return aim;
+        }
+
+        // Towers only get their `Tower` once their prefab is applied, which hasn't happened yet
+        // for one that was just placed
+        let towers = world.read_storage::<Tower>();
+        let tower = (
+            &world.read_storage::<TowerKind>(),
+            towers.maybe(),
+            &transforms,
+        )
+            .join()
+            .filter(|(_, tower, _)| tower.is_none_or(|t| !t.active))
+            .map(|(_, _, transform)| position(transform))
+            .next();
+        match tower {
+            Some(tower) if (tower - player_position).norm() <= ENTER_DISTANCE => {
+                self.queued.push_back(TickInput::default());
+                TickInput {
+                    activate: true,
+                    ..Default::default()
+                }
+            }
+            Some(tower) => {
+                let direction: Vector2<f32> = (tower - player_position).normalize();
+                TickInput {
+                    sideways: direction.x,
+                    forwards: direction.y,
+                    ..Default::default()
+                }
+            }
+            None => self.build_tower(world),
+        }
+    }
+
+    // Picks a tower from the HUD and places it, over the next few ticks
+    fn build_tower(&mut self, world: &World) -> TickInput {
+        let buildable = Buildable::Tower(TowerType::Standard);
+        let cost = buildable.cost(&world.read_resource::<GameplayConfig>());
+        if world.read_resource::<Economy>().money < cost {
+            return TickInput::default();
+        }
+        let spot = match tower_spot(&world.read_resource::<LevelMap>()) {
+            Some(s) => s,
+            None => return TickInput::default(),
+        };
+
+        let aim = TickInput {
+            mouse: Some(spot),
+            ..Default::default()
+        };
+        self.queued.push_back(TickInput {
+            place: true,
+            ..aim.clone()
+        });
+        self.queued.push_back(aim.clone());
+        TickInput {
+            select: Some(buildable),
+            ..aim
+        }
+    }
+}
+
+// Halfway along the path from the first spawn to the first exit, where enemies are bound to
+// walk past
+fn tower_spot(level_map: &LevelMap) -> Option<Point2<f32>> {
+    let (_, spawn) = level_map.spawns().next()?;
+    let (_, exit) = level_map.exits().next()?;
+    let path = level_map.path_to(level_map.cell_to_world(spawn), exit)?;
+    path.get(path.len() / 2).copied()
+}
@@ -1,3 +1,4 @@
+use crate::{pathfinding::Cell, resources::EnemyType};
 use amethyst::{
     assets::PrefabData,
     derive::PrefabData,
@@ -13,10 +14,15 @@ use serde::{Deserialize, Serialize};
 #[serde(deny_unknown_fields)]
 pub struct Guided {
     pub speed: f32,
-    // Waypoints are all supposed to be destinations, so the initial position should
-    // not be included
-    pub waypoints: Vec<Point2<f32>>,
+    // The path is computed by the pathfinding system from the level map. Waypoints are all
+    // supposed to be destinations, so the initial position is not included
+    #[serde(default)]
+    pub path: Vec<Point2<f32>>,
+    #[serde(default)]
     pub curr_waypoint: usize,
+    // Revision of the level map the path was computed with
+    #[serde(skip)]
+    pub map_revision: Option<u64>,
 }
 
 #[derive(Clone, Component, Debug, Deserialize, Serialize, PrefabData)]
@@ -25,4 +31,34 @@ pub struct Guided {
 #[serde(deny_unknown_fields)]
 pub struct Hitable {
     pub health: f32,
+    pub max_health: f32,
+}
+
+// What the player gets for taking the enemy down
+#[derive(Clone, Component, Debug, Deserialize, Serialize, PrefabData)]
+#[prefab(Component)]
+#[storage(DenseVecStorage)]
+#[serde(deny_unknown_fields)]
+pub struct Bounty {
+    pub money: u32,
+    pub score: u32,
+}
+
+// Where the enemy is headed. It's set when spawning, as it depends on the wave and level
+#[derive(Clone, Component, Debug)]
+#[storage(DenseVecStorage)]
+pub struct Destination {
+    pub exit: Cell,
+}
+
+// Which prefab the enemy was spawned from, so that it can be saved and spawned again
+#[derive(Clone, Copy, Component, Debug)]
+#[storage(DenseVecStorage)]
+pub struct EnemyKind(pub EnemyType);
+
+// Bar showing how much health a Hitable has left. It lives on a child entity of its owner
+#[derive(Clone, Component, Debug)]
+#[storage(DenseVecStorage)]
+pub struct HealthBar {
+    pub owner: Entity,
 }
@@ -1,13 +1,17 @@
 mod enemy;
 mod physics;
 mod player;
+mod restore;
 mod splash;
 mod tower;
+mod wall;
 
 pub use self::{
-    enemy::{Guided, Hitable},
+    enemy::{Bounty, Destination, EnemyKind, Guided, HealthBar, Hitable},
     physics::Velocity,
     player::ControlledCharacter,
+    restore::Restore,
     splash::Splash,
-    tower::{Bullet, Tower, TowerDirection},
+    tower::{Bullet, Tower, TowerDirection, TowerKind},
+    wall::Wall,
 };
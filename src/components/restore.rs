@@ -0,0 +1,26 @@
+use amethyst::ecs::{storage::DenseVecStorage, Component};
+use nalgebra::Vector3;
+
+// Values to put on an entity that differ from its prefab's, mostly ones restored from a save. They
+// can only be applied once the entity's prefab has been, as it would otherwise overwrite them with
+// its own defaults
+#[derive(Clone, Component, Debug)]
+#[storage(DenseVecStorage)]
+pub enum Restore {
+    Enemy {
+        health: f32,
+        // Older saves don't have it, in which case the prefab's is kept
+        max_health: Option<f32>,
+    },
+    // An enemy spawned with this many times the health its prefab gives it
+    Toughened {
+        factor: f32,
+    },
+    Tower {
+        active: bool,
+    },
+    Player {
+        translation: Vector3<f32>,
+        locked: bool,
+    },
+}
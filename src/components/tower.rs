@@ -1,3 +1,4 @@
+use crate::resources::TowerType;
 use amethyst::{
     assets::PrefabData,
     derive::PrefabData,
@@ -25,6 +26,11 @@ pub struct Tower {
     pub active: bool,
 }
 
+// Which prefab the tower was built from, so that it can be saved and built again
+#[derive(Clone, Copy, Component, Debug)]
+#[storage(DenseVecStorage)]
+pub struct TowerKind(pub TowerType);
+
 #[derive(Clone, Component, Debug, Deserialize, Serialize, PrefabData)]
 #[prefab(Component)]
 #[storage(DenseVecStorage)]
@@ -0,0 +1,5 @@
+use amethyst::ecs::{storage::NullStorage, Component};
+
+#[derive(Clone, Component, Debug, Default)]
+#[storage(NullStorage)]
+pub struct Wall;
@@ -0,0 +1,168 @@
+use crate::{
+    components::{Destination, EnemyKind, Restore, TowerKind},
+    headless,
+    input::TickInput,
+    pathfinding::Cell,
+    prefabs::{EnemyPrefab, PlayerPrefab, TowerPrefab},
+    resources::{
+        self, EnemyPrefabSet, EnemyType, GameRng, TowerPrefabSet, TowerType, WaveSchedule,
+    },
+    simulation::{self, Simulation},
+};
+use amethyst::{
+    assets::{Handle, Prefab},
+    core::Transform,
+    ecs::{Entities, Entity, Read, World, WorldExt, WriteStorage},
+    prelude::Builder,
+    utils::application_root_dir,
+};
+use nalgebra::{Point2, Vector3};
+
+/// Runs the gameplay systems on a level without a window, so that tests can drive them with
+/// whatever input they need. Nothing spawns on its own: the level's waves are left out, and
+/// entities are added through the harness.
+pub struct Harness {
+    pub world: World,
+    simulation: Simulation,
+    player_prefab: Handle<Prefab<PlayerPrefab>>,
+}
+
+impl Harness {
+    pub fn new(level: &str) -> Self {
+        let (mut world, player_prefab) =
+            headless::headless_world().expect("Failed to set up a headless world");
+        let mut level = resources::load_level(
+            application_root_dir()
+                .expect("Failed to find the application root")
+                .join("assets")
+                .join("levels")
+                .join(format!("{}.ron", level)),
+        )
+        .expect("Failed to load the level");
+        level.waves = WaveSchedule::new(vec![]);
+
+        let simulation = Simulation::new(&mut world);
+        simulation::insert_level(&mut world, level);
+        world.insert(GameRng::new(0));
+        Harness {
+            world,
+            simulation,
+            player_prefab,
+        }
+    }
+
+    pub fn step(&mut self, input: TickInput) {
+        self.world.insert(input);
+        self.simulation.step(&mut self.world);
+    }
+
+    pub fn run(&mut self, ticks: u32, input: &TickInput) {
+        for _ in 0..ticks {
+            self.step(input.clone());
+        }
+    }
+
+    // Presses and releases an action, as most of them only go off on release. `press` picks the
+    // action out of the input
+    pub fn tap(&mut self, input: &TickInput, press: impl Fn(&mut TickInput)) {
+        let mut pressed = input.clone();
+        press(&mut pressed);
+        self.step(pressed);
+        self.step(input.clone());
+    }
+
+    // Centre of the cell in world coordinates
+    pub fn cell(&self, cell: Cell) -> Point2<f32> {
+        self.world
+            .read_resource::<resources::LevelMap>()
+            .cell_to_world(cell)
+    }
+
+    // Prefabs only get applied on the next step, as they would in the game
+    pub fn spawn_player(&mut self, position: Point2<f32>) -> Entity {
+        self.world
+            .create_entity()
+            .with(self.player_prefab.clone())
+            .with(Restore::Player {
+                translation: Vector3::new(position.x, position.y, 0.6),
+                locked: false,
+            })
+            .build()
+    }
+
+    pub fn add_tower(&mut self, position: Point2<f32>, active: bool) -> Entity {
+        let tower = self.world.exec(
+            #[allow(clippy::type_complexity)]
+            |(entities, mut tower_prefabs, mut transforms, mut tower_kinds, tower_prefab_set): (
+                Entities,
+                WriteStorage<Handle<Prefab<TowerPrefab>>>,
+                WriteStorage<Transform>,
+                WriteStorage<TowerKind>,
+                Read<TowerPrefabSet>,
+            )| {
+                tower_prefab_set.add_tower(
+                    TowerType::Standard,
+                    position,
+                    &entities,
+                    &mut tower_prefabs,
+                    &mut transforms,
+                    &mut tower_kinds,
+                )
+            },
+        );
+        let tower = tower.expect("Failed to add a tower");
+        self.world
+            .write_storage::<Restore>()
+            .insert(tower, Restore::Tower { active })
+            .expect("Failed to add a tower");
+        tower
+    }
+
+    pub fn spawn_enemy(&mut self, position: Point2<f32>, exit: &str) -> Entity {
+        let exit = self
+            .world
+            .read_resource::<resources::LevelMap>()
+            .exit(exit)
+            .expect("Unknown exit");
+        self.world
+            .exec(
+                #[allow(clippy::type_complexity)]
+                |(
+                    entities,
+                    mut enemy_prefabs,
+                    mut transforms,
+                    mut destinations,
+                    mut enemy_kinds,
+                    enemy_prefab_set,
+                ): (
+                    Entities,
+                    WriteStorage<Handle<Prefab<EnemyPrefab>>>,
+                    WriteStorage<Transform>,
+                    WriteStorage<Destination>,
+                    WriteStorage<EnemyKind>,
+                    Read<EnemyPrefabSet>,
+                )| {
+                    enemy_prefab_set.add_enemy(
+                        EnemyType::Standard,
+                        position,
+                        exit,
+                        &entities,
+                        &mut enemy_prefabs,
+                        &mut transforms,
+                        &mut destinations,
+                        &mut enemy_kinds,
+                    )
+                },
+            )
+            .expect("Failed to spawn an enemy")
+    }
+
+    pub fn position(&self, entity: Entity) -> Point2<f32> {
+        let transforms = self.world.read_storage::<Transform>();
+        let translation = transforms
+            .get(entity)
+            .expect("Entity has no transform")
+            .translation();
+        Point2::new(translation.x, translation.y)
+    }
+}
@@ -0,0 +1,372 @@
+use crate::{
+    balance::Tuning,
+    bot::{Bot, BotSettings},
+    components::{Restore, TowerKind, Wall},
+    input::TickInput,
+    pathfinding::Cell,
+    prefabs::{self, Headless, PlayerPrefab, TowerPrefab, WallPrefab},
+    replay,
+    resources::{
+        self, BulletPrefabSet, Economy, EndlessWaves, EnemyPrefabSet, GameRng, GameplayConfig,
+        LevelMap, Lives, RngStream, TowerPrefabSet, TowerType, WallPrefabSet, WaveSchedule,
+    },
+    simulation::{self, RunOutcome, Simulation},
+    state::RunSummary,
+};
+use amethyst::{
+    assets::{Handle, Loader, Prefab, ProgressCounter},
+    config::Config,
+    core::{ArcThreadPool, Time, Transform},
+    ecs::{
+        rayon::ThreadPoolBuilder, DispatcherBuilder, Entities, Read, ReadExpect, World, WorldExt,
+        WriteExpect, WriteStorage,
+    },
+    prelude::Builder,
+    utils::application_root_dir,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+/// A run to simulate without a window, set up from a file rather than played.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    // Name of the file in assets/levels to play, without the extension
+    level: String,
+    // RON file with waves to use instead of the level's own. Relative to the scenario file
+    #[serde(default)]
+    waves: Option<PathBuf>,
+    #[serde(default)]
+    towers: Vec<ScenarioTower>,
+    #[serde(default)]
+    walls: Vec<Cell>,
+    // Simulated seconds after which the run is stopped, if it isn't over by then
+    seconds: f32,
+    // Replay to take the player's input from, as recorded by the game. Relative to the scenario
+    // file
+    #[serde(default)]
+    replay: Option<PathBuf>,
+    // Lets the bot play instead of a replay. Without either the player stands still
+    #[serde(default)]
+    bot: Option<BotSettings>,
+    // Seed of anything random in the run. Replays bring their own
+    #[serde(default)]
+    seed: Option<u64>,
+    // Scales the prefabs' and waves' values, see `balance`
+    #[serde(default)]
+    tuning: Tuning,
+}
+
+impl Scenario {
+    // Same scenario, but played by the given bot with the given tuning
+    pub fn with_bot(self, bot: BotSettings, tuning: Tuning) -> Self {
+        Scenario {
+            replay: None,
+            bot: Some(bot),
+            tuning,
+            ..self
+        }
+    }
+
+    // File the scenario's waves come from, which is the level's own unless it has its own
+    pub fn waves_file(&self, scenario_path: &Path) -> anyhow::Result<PathBuf> {
+        Ok(match self.waves {
+            Some(ref waves_path) => relative_to(scenario_path).join(waves_path),
+            None => level_path(&self.level)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct ScenarioTower {
+    #[serde(default = "default_tower_type")]
+    tower_type: TowerType,
+    cell: Cell,
+    // Whether the tower starts out manned
+    #[serde(default)]
+    active: bool,
+}
+
+fn default_tower_type() -> TowerType {
+    TowerType::Standard
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    // Missing if the time ran out before the run was over
+    pub outcome: Option<RunOutcome>,
+    lives: u32,
+    money: u32,
+    #[serde(flatten)]
+    pub summary: RunSummary,
+}
+
+/// Runs the scenario in the given file as fast as possible and prints how it went, as JSON.
+pub fn run(scenario_path: &Path) -> anyhow::Result<()> {
+    let report = run_scenario(scenario_path)?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+pub fn run_scenario(scenario_path: &Path) -> anyhow::Result<Report> {
+    run_loaded(&load_scenario(scenario_path)?, scenario_path)
+}
+
+pub fn load_scenario(scenario_path: &Path) -> anyhow::Result<Scenario> {
+    Scenario::load(scenario_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read scenario {:?}: {}", scenario_path, e))
+}
+
+// Runs a scenario that's already been read from the given path
+pub fn run_loaded(scenario: &Scenario, scenario_path: &Path) -> anyhow::Result<Report> {
+    let (mut world, player_prefab) = headless_world()?;
+    simulate(
+        &mut world,
+        scenario,
+        relative_to(scenario_path),
+        player_prefab,
+    )
+}
+
+// Paths in a scenario are relative to its file
+fn relative_to(scenario_path: &Path) -> &Path {
+    scenario_path.parent().unwrap_or_else(|| Path::new(""))
+}
+
+fn level_path(level: &str) -> anyhow::Result<PathBuf> {
+    Ok(application_root_dir()?
+        .join("assets")
+        .join("levels")
+        .join(format!("{}.ron", level)))
+}
+
+/// Sets up a world with no window, renderer or audio, with all the gameplay prefabs loaded into
+/// it. The player's prefab isn't kept anywhere in the world, so it's handed back.
+pub fn headless_world() -> anyhow::Result<(World, Handle<Prefab<PlayerPrefab>>)> {
+    let mut world = World::new();
+    let pool: ArcThreadPool = Arc::new(ThreadPoolBuilder::new().build()?);
+    world.insert(Loader::new(
+        application_root_dir()?.join("assets"),
+        pool.clone(),
+    ));
+    world.insert(pool.clone());
+    world.insert(Time::default());
+    world.insert(Headless);
+    world.insert(resources::load_gameplay_config(
+        application_root_dir()?.join("config").join("gameplay.ron"),
+    )?);
+
+    let mut prefab_loaders = simulation::with_prefab_loaders(DispatcherBuilder::new(), &mut world)
+        .with_pool(pool)
+        .build();
+    prefab_loaders.setup(&mut world);
+
+    let mut progress_counter = ProgressCounter::new();
+    let bullet_prefab_set = BulletPrefabSet::new(&mut world, &mut progress_counter);
+    world.insert(bullet_prefab_set);
+    let tower_prefab_set = TowerPrefabSet::new(&mut world, &mut progress_counter);
+    world.insert(tower_prefab_set);
+    let enemy_prefab_set = EnemyPrefabSet::new(&mut world, &mut progress_counter);
+    world.insert(enemy_prefab_set);
+    let wall_prefab_set = WallPrefabSet::new(&mut world, &mut progress_counter);
+    world.insert(wall_prefab_set);
+    let player_prefab = prefabs::load_player(&mut world, &mut progress_counter);
+
+    while !progress_counter.is_complete() {
+        anyhow::ensure!(
+            progress_counter.errors().is_empty(),
+            "Failed to load prefabs: {:?}",
+            progress_counter.errors()
+        );
+        prefab_loaders.dispatch(&world);
+        world.maintain();
+        thread::sleep(Duration::from_millis(1));
+    }
+    Ok((world, player_prefab))
+}
+
+fn simulate(
+    world: &mut World,
+    scenario: &Scenario,
+    relative_to: &Path,
+    player_prefab: Handle<Prefab<PlayerPrefab>>,
+) -> anyhow::Result<Report> {
+    let mut level = resources::load_level(level_path(&scenario.level)?)?;
+    if let Some(ref waves_path) = scenario.waves {
+        let waves = resources::load_waves(relative_to.join(waves_path))?;
+        level.map.check_waves(&waves)?;
+        level.waves = WaveSchedule::new(waves);
+    }
+    level.waves = WaveSchedule::new(scenario.tuning.apply_to_waves(level.waves.waves()));
+    scenario.tuning.apply_to_prefabs(world);
+    scenario
+        .tuning
+        .apply_to_config(&mut world.write_resource::<GameplayConfig>());
+    let mut replay = match scenario.replay {
+        Some(ref replay_path) => {
+            let replay = replay::load(&relative_to.join(replay_path))?;
+            anyhow::ensure!(
+                replay.level == scenario.level,
+                "Replay is of level {:?}, not {:?}",
+                replay.level,
+                scenario.level
+            );
+            Some(replay)
+        }
+        None => None,
+    };
+    anyhow::ensure!(
+        replay.is_none() || scenario.bot.is_none(),
+        "Scenario can't have both a replay and a bot"
+    );
+    anyhow::ensure!(
+        replay.is_none() || scenario.seed.is_none(),
+        "Scenario can't have both a replay and a seed, the replay's is used"
+    );
+    let mut bot = scenario.bot.clone().map(Bot::new);
+
+    // Same as in the game, nothing can be added to entities before this
+    let mut simulation = Simulation::new(world);
+    // Replays need the same numbers they were played with. Anything else gets the same ones every
+    // time it's simulated
+    let seed = replay.as_ref().map(|r| r.seed).or(scenario.seed);
+    let rng = GameRng::new(seed.unwrap_or(0));
+    let endless = replay
+        .as_ref()
+        .filter(|r| r.endless)
+        .map(|_| EndlessWaves::new(rng.stream_seed(RngStream::EndlessWaves), &level.waves));
+    simulation::insert_level(world, level);
+    world.insert(rng);
+    if let Some(endless) = endless {
+        world.insert(endless);
+    }
+    world.create_entity().with(player_prefab).build();
+    place_walls(world, &scenario.walls)?;
+    place_towers(world, &scenario.towers)?;
+
+    let step = world.read_resource::<resources::SimulationClock>().step;
+    let ticks = (scenario.seconds / step).ceil() as u64;
+    let mut outcome = None;
+    for _ in 0..ticks {
+        let input = match (&mut replay, &mut bot) {
+            (Some(replay), _) => replay.next_input().unwrap_or_default(),
+            (None, Some(bot)) => bot.next_input(world, step),
+            (None, None) => TickInput::default(),
+        };
+        world.insert::<TickInput>(input);
+        simulation.step(world);
+
+        outcome = simulation::outcome(world);
+        if outcome.is_some() {
+            break;
+        }
+    }
+
+    Ok(Report {
+        outcome,
+        lives: world.read_resource::<Lives>().remaining,
+        money: world.read_resource::<Economy>().money,
+        summary: RunSummary::new(world),
+    })
+}
+
+fn place_walls(world: &mut World, cells: &[Cell]) -> anyhow::Result<()> {
+    world.exec(
+        #[allow(clippy::type_complexity)]
+        |(
+            entities,
+            mut wall_prefabs,
+            mut walls,
+            mut transforms,
+            mut level_map,
+            wall_prefab_set,
+        ): (
+            Entities,
+            WriteStorage<Handle<Prefab<WallPrefab>>>,
+            WriteStorage<Wall>,
+            WriteStorage<Transform>,
+            WriteExpect<LevelMap>,
+            Read<WallPrefabSet>,
+        )| {
+            for cell in cells {
+                anyhow::ensure!(
+                    level_map.can_block(*cell, &[]),
+                    "Wall at {:?} is not on a free cell, or blocks the path",
+                    cell
+                );
+                level_map.set_walkable(*cell, false);
+                wall_prefab_set.add_wall(
+                    level_map.cell_to_world(*cell),
+                    &entities,
+                    &mut wall_prefabs,
+                    &mut walls,
+                    &mut transforms,
+                )?;
+            }
+            Ok(())
+        },
+    )
+}
+
+fn place_towers(world: &mut World, towers: &[ScenarioTower]) -> anyhow::Result<()> {
+    world.exec(
+        #[allow(clippy::type_complexity)]
+        |(
+            entities,
+            mut tower_prefabs,
+            mut transforms,
+            mut tower_kinds,
+            mut restores,
+            level_map,
+            tower_prefab_set,
+        ): (
+            Entities,
+            WriteStorage<Handle<Prefab<TowerPrefab>>>,
+            WriteStorage<Transform>,
+            WriteStorage<TowerKind>,
+            WriteStorage<Restore>,
+            ReadExpect<LevelMap>,
+            Read<TowerPrefabSet>,
+        )| {
+            for tower in towers {
+                let entity = tower_prefab_set.add_tower(
+                    tower.tower_type,
+                    level_map.cell_to_world(tower.cell),
+                    &entities,
+                    &mut tower_prefabs,
+                    &mut transforms,
+                    &mut tower_kinds,
+                )?;
+                restores.insert(
+                    entity,
+                    Restore::Tower {
+                        active: tower.active,
+                    },
+                )?;
+            }
+            Ok(())
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // If this starts failing, either the level got harder or something the bot relies on broke
+    #[test]
+    fn bot_wins_level1() {
+        let scenario = application_root_dir()
+            .unwrap()
+            .join("scenarios")
+            .join("level1_bot.ron");
+        let report = run_scenario(&scenario).unwrap();
+        assert_eq!(report.outcome, Some(RunOutcome::Victory));
+    }
+}
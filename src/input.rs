@@ -1,8 +1,9 @@
 use std::fmt::{self, Display};
 
+use crate::resources::{Buildable, GameSpeed};
 use amethyst::{
     core::{geometry::Plane, Transform},
-    ecs::{Entities, Join, Read, ReadExpect, ReadStorage, WriteStorage},
+    ecs::{Entities, Join, Read, ReadExpect, ReadStorage, World, Write, WriteStorage},
     input::{BindingTypes, InputHandler},
     renderer::{ActiveCamera, Camera},
     window::ScreenDimensions,
@@ -21,6 +22,8 @@ pub enum ActionBinding {
     Fire,
     Activate,
     Place,
+    CallWave,
+    ChangeSpeed,
 }
 
 impl Display for AxisBinding {
@@ -43,8 +46,91 @@ impl BindingTypes for GameBindingTypes {
     type Action = ActionBinding;
 }
 
+// Everything gameplay systems get to know about the player's input on a given tick. It's captured
+// from the devices once per tick (or read back from a replay), so that the simulation never has to
+// look at them directly
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct TickInput {
+    pub forwards: f32,
+    pub sideways: f32,
+    pub fire: bool,
+    pub activate: bool,
+    pub place: bool,
+    // Where the mouse points to in the world
+    pub mouse: Option<Point2<f32>>,
+    // Buildable picked from the HUD since the last tick
+    pub select: Option<Buildable>,
+    // Only for show, the simulation never looks at it. Part of the input so that replays go
+    // through the run at the same speeds it was played at
+    #[serde(default)]
+    pub speed: GameSpeed,
+    // Starts the next wave early, once let go of
+    #[serde(default)]
+    pub call_wave: bool,
+}
+
+// Buildable picked from the HUD, waiting for the next tick to pick it up
+#[derive(Default)]
+pub struct PendingSelection(pub Option<Buildable>);
+
+// Whether the HUD's next wave button was clicked since the last tick
+#[derive(Default)]
+pub struct PendingWaveCall(pub bool);
+
 // Useful input utils
 
+pub fn capture_tick_input(world: &mut World) -> TickInput {
+    world.exec(
+        #[allow(clippy::type_complexity)]
+        |(
+            entities,
+            input,
+            transforms,
+            cameras,
+            active_camera,
+            screen_dimensions,
+            pending_selection,
+            pending_wave_call,
+            speed,
+        ): (
+            Entities<'_>,
+            Read<'_, InputHandler<GameBindingTypes>>,
+            WriteStorage<'_, Transform>,
+            ReadStorage<'_, Camera>,
+            Read<'_, ActiveCamera>,
+            ReadExpect<'_, ScreenDimensions>,
+            Option<Write<'_, PendingSelection>>,
+            Option<Write<'_, PendingWaveCall>>,
+            Read<'_, GameSpeed>,
+        )| {
+            TickInput {
+                forwards: input.axis_value(&AxisBinding::Forwards).unwrap_or(0.0),
+                sideways: input.axis_value(&AxisBinding::Sideways).unwrap_or(0.0),
+                fire: input.action_is_down(&ActionBinding::Fire).unwrap_or(false),
+                activate: input
+                    .action_is_down(&ActionBinding::Activate)
+                    .unwrap_or(false),
+                place: input.action_is_down(&ActionBinding::Place).unwrap_or(false),
+                mouse: get_mouse_projection(
+                    &entities,
+                    &input,
+                    &transforms,
+                    &cameras,
+                    &active_camera,
+                    &screen_dimensions,
+                ),
+                select: pending_selection.and_then(|mut p| p.0.take()),
+                speed: *speed,
+                // The button counts as a tap, so it goes off on the tick after
+                call_wave: input
+                    .action_is_down(&ActionBinding::CallWave)
+                    .unwrap_or(false)
+                    || pending_wave_call.is_some_and(|mut p| std::mem::take(&mut p.0)),
+            }
+        },
+    )
+}
+
 pub fn get_mouse_projection<'s>(
     entities: &Entities<'s>,
     input: &Read<'s, InputHandler<GameBindingTypes>>,
@@ -0,0 +1,143 @@
+use amethyst::utils::application_root_dir;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, fs, path::PathBuf};
+
+// Runs kept for each level
+const ENTRIES_PER_LEVEL: usize = 10;
+
+/// An endless run, as it's remembered on the leaderboard.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Entry {
+    pub level: String,
+    // Highest wave reached, counting from 1
+    pub wave: usize,
+    pub score: u32,
+    // So that the same run can be played again, with --seed
+    pub seed: u64,
+}
+
+/// The best endless runs on each level, best first.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Leaderboard {
+    entries: Vec<Entry>,
+}
+
+impl Leaderboard {
+    // Adds the run if it's among the best on its level, returning where it placed, counting from 1.
+    // Runs go by the wave they reached, then by score
+    pub fn add(&mut self, entry: Entry) -> Option<usize> {
+        let rank = self
+            .level(&entry.level)
+            .take_while(|e| (e.wave, e.score) >= (entry.wave, entry.score))
+            .count();
+        if rank >= ENTRIES_PER_LEVEL {
+            return None;
+        }
+
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.level == entry.level && (e.wave, e.score) < (entry.wave, entry.score))
+            .unwrap_or(self.entries.len());
+        let level = entry.level.clone();
+        self.entries.insert(index, entry);
+        // Whatever got pushed off the level's list goes away for good
+        if let Some(index) = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.level == level)
+            .map(|(index, _)| index)
+            .nth(ENTRIES_PER_LEVEL)
+        {
+            self.entries.remove(index);
+        }
+        Some(rank + 1)
+    }
+
+    pub fn level<'a>(&'a self, level: &'a str) -> impl Iterator<Item = &'a Entry> {
+        self.entries.iter().filter(move |e| e.level == level)
+    }
+
+    // The top of the level's list, pointing out the run that placed at `rank` if there is one
+    pub fn describe(&self, level: &str, rank: Option<usize>, count: usize) -> String {
+        let mut text = format!("Best endless runs on {}:", level);
+        for (index, entry) in self.level(level).take(count).enumerate() {
+            let marker = if rank == Some(index + 1) { " <" } else { "" };
+            let _ = write!(
+                text,
+                "\n{}. Wave {}, {} points{}",
+                index + 1,
+                entry.wave,
+                entry.score,
+                marker
+            );
+        }
+        text
+    }
+}
+
+fn leaderboard_path() -> anyhow::Result<PathBuf> {
+    Ok(application_root_dir()?
+        .join("saves")
+        .join("leaderboard.json"))
+}
+
+/// Reads the leaderboard, which is empty until a run has been put on it.
+pub fn read() -> anyhow::Result<Leaderboard> {
+    let path = leaderboard_path()?;
+    if !path.exists() {
+        return Ok(Leaderboard::default());
+    }
+    let contents = fs::read_to_string(&path)?;
+    serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("Failed to read leaderboard {:?}: {}", path, e))
+}
+
+/// Puts a finished run on the leaderboard, if it made it, and returns the updated leaderboard
+/// along with where the run placed.
+pub fn record(entry: Entry) -> anyhow::Result<(Leaderboard, Option<usize>)> {
+    let mut leaderboard = read()?;
+    let rank = leaderboard.add(entry);
+    if rank.is_some() {
+        let path = leaderboard_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&leaderboard)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write leaderboard {:?}: {}", path, e))?;
+    }
+    Ok((leaderboard, rank))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: &str, wave: usize, score: u32) -> Entry {
+        Entry {
+            level: level.to_string(),
+            wave,
+            score,
+            seed: 0,
+        }
+    }
+
+    #[test]
+    fn keeps_the_best_runs_of_each_level() {
+        let mut leaderboard = Leaderboard::default();
+        for wave in 1..=ENTRIES_PER_LEVEL {
+            leaderboard.add(entry("level1", wave, 100));
+        }
+        leaderboard.add(entry("level2", 1, 0));
+
+        assert_eq!(leaderboard.add(entry("level1", 1, 50)), None);
+        assert_eq!(leaderboard.add(entry("level1", 5, 200)), Some(6));
+        assert_eq!(leaderboard.level("level1").count(), ENTRIES_PER_LEVEL);
+        assert_eq!(
+            leaderboard.level("level1").last(),
+            Some(&entry("level1", 2, 100))
+        );
+        assert_eq!(leaderboard.level("level2").count(), 1);
+    }
+}
@@ -2,6 +2,7 @@ use amethyst::{
     animation::AnimationBundle,
     assets::PrefabLoaderSystemDesc,
     audio::AudioBundle,
+    config::Config,
     core::transform::TransformBundle,
     input::InputBundle,
     renderer::{
@@ -12,38 +13,88 @@ use amethyst::{
     },
     ui::{RenderUi, UiBundle},
     utils::application_root_dir,
-    Application, GameDataBuilder,
+    window::DisplayConfig,
+    Application, GameDataBuilder, LoggerConfig,
 };
+use log::{error, LevelFilter};
+use options::LaunchOptions;
+use std::{path::Path, process, time::Duration};
 
 mod animation;
 mod audio;
+mod balance;
+mod bot;
 mod components;
+#[cfg(test)]
+mod harness;
+mod headless;
 mod input;
+mod leaderboard;
+mod options;
+mod pathfinding;
 mod prefabs;
+mod replay;
 mod resources;
+mod save;
+mod simulation;
 mod state;
 mod systems;
 mod util;
+mod validate;
 
 fn main() -> amethyst::Result<()> {
-    amethyst::start_logger(Default::default());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("headless") => run_headless(&args[1..]),
+        Some("balance") => run_balance(&args[1..]),
+        Some("validate") => run_validate(&args[1..]),
+        _ => (),
+    }
+
+    let options = match LaunchOptions::parse(&args) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, options::USAGE);
+            process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", options::USAGE);
+        return Ok(());
+    }
+    let levels_dir = application_root_dir()?.join("assets").join("levels");
+    if let Err(e) = options.check_level(&levels_dir) {
+        eprintln!("{}", e);
+        process::exit(2);
+    }
+
+    let mut logger_config = LoggerConfig::default();
+    if let Some(level) = options.log_level {
+        logger_config.level_filter = level;
+    }
+    amethyst::start_logger(logger_config);
 
     let app_root = application_root_dir()?;
     let assets_dir = app_root.join("assets");
-    let display_config_path = app_root.join("config").join("display.ron");
+    let mut display_config = DisplayConfig::load(app_root.join("config").join("display.ron"))?;
+    // The window is a fixed size, so the override pins it just like display.ron does
+    if let Some(size) = options.window_size {
+        display_config.dimensions = Some(size);
+        display_config.min_dimensions = Some(size);
+        display_config.max_dimensions = Some(size);
+    }
     let bindings_path = app_root.join("config").join("bindings.ron");
+    let gameplay =
+        match resources::load_gameplay_config(app_root.join("config").join("gameplay.ron")) {
+            Ok(g) => g,
+            Err(e) => {
+                error!("{}", e);
+                process::exit(1);
+            }
+        };
 
+    // Loaders for gameplay prefabs are part of the simulation, see `Simulation`
     let game_data = GameDataBuilder::default()
-        .with_system_desc(
-            PrefabLoaderSystemDesc::<prefabs::TowerPrefab>::default(),
-            "tower_loader",
-            &[],
-        )
-        .with_system_desc(
-            PrefabLoaderSystemDesc::<prefabs::BulletPrefab>::default(),
-            "bullet_loader",
-            &[],
-        )
         .with_system_desc(
             PrefabLoaderSystemDesc::<prefabs::SplashAnimationPrefab>::default(),
             "splash_loader",
@@ -54,16 +105,6 @@ fn main() -> amethyst::Result<()> {
             "background_loader",
             &[],
         )
-        .with_system_desc(
-            PrefabLoaderSystemDesc::<prefabs::PlayerPrefab>::default(),
-            "player_loader",
-            &[],
-        )
-        .with_system_desc(
-            PrefabLoaderSystemDesc::<prefabs::EnemyPrefab>::default(),
-            "enemy_loader",
-            &[],
-        )
         .with_system_desc(
             systems::UiEventHandlerSystemDesc::default(),
             "ui_event_handler",
@@ -86,62 +127,89 @@ fn main() -> amethyst::Result<()> {
         .with_bundle(
             RenderingBundle::<DefaultBackend>::new()
                 .with_plugin(
-                    RenderToWindow::from_config_path(display_config_path)?
-                        .with_clear([0., 0., 0., 1.]),
+                    RenderToWindow::from_config(display_config).with_clear([0., 0., 0., 1.]),
                 )
                 .with_plugin(RenderFlat2D::default())
                 .with_plugin(RenderUi::default()),
         )?
         .with_bundle(AudioBundle::default())?
-        .with(
-            systems::PlayerControlSystem::default(),
-            "player_control_system",
-            &[],
-        )
-        .with(
-            systems::ShooterControlSystem::default(),
-            "shooter_control_system",
+        .with(systems::TowerDirectionSystem, "tower_direction_system", &[])
+        .with(systems::HealthBarSystem, "health_bar_system", &[])
+        .with(systems::HudSystem, "hud_system", &[])
+        .with(systems::CameraFollowSystem, "camera_follow_system", &[]);
+    // Changed prefabs, gameplay config and waves are picked up without a restart. They go in
+    // between ticks, but whenever the files happen to change, so runs played with it can't be
+    // replayed as they went
+    let game_data = if options.hot_reload {
+        game_data.with(
+            systems::ConfigReloadSystem::default(),
+            "config_reload_system",
             &[],
         )
-        .with(
-            systems::TowerDirectionSystem,
-            "tower_direction_system",
-            &["shooter_control_system"],
-        )
-        .with(
-            systems::BulletSystem,
-            "bullet_system",
-            &["shooter_control_system"],
-        )
-        .with(systems::EnemyMovementSystem, "enemy_movement_system", &[])
-        .with(
-            systems::PhysicsSystem,
-            "physics_system",
-            &[
-                "bullet_system",
-                "shooter_control_system",
-                "player_control_system",
-                "enemy_movement_system",
-            ],
-        )
-        .with(
-            systems::PlacementSystem::default(),
-            "placement_system",
-            &["ui_event_handler"],
-        )
-        .with(
-            systems::EnemySpawnSystem::default(),
-            "enemy_spawn_system",
-            &[],
-        )
-        .with(
-            systems::CameraFollowSystem,
-            "camera_follow_system",
-            &["physics_system"],
-        );
+    } else {
+        game_data
+    };
 
-    let mut game = Application::new(assets_dir, state::Loading::default(), game_data)?;
+    // Gameplay only ever moves forward in steps of this length, see `Simulation`
+    let show_splash = !options.no_splash;
+    let mut game = Application::build(assets_dir, state::Loading::new(show_splash))?
+        .with_resource(gameplay)
+        .with_resource(options)
+        .with_fixed_step_length(Duration::from_secs_f32(resources::SIMULATION_STEP))
+        .build(game_data)?;
     game.run();
 
     Ok(())
 }
+
+// `towpow headless <scenario>` simulates a run without any window, see `headless::run`
+fn run_headless(args: &[String]) -> ! {
+    let scenario = match args {
+        [scenario] => scenario,
+        _ => {
+            eprintln!("Usage: towpow headless <scenario.ron>");
+            process::exit(2);
+        }
+    };
+    run_tool(|| headless::run(Path::new(scenario)))
+}
+
+// `towpow balance <config> [--write]` searches for values that match difficulty targets, see
+// `balance::run`
+fn run_balance(args: &[String]) -> ! {
+    let (config, write) = match args {
+        [config] => (config, false),
+        [config, flag] if flag == "--write" => (config, true),
+        _ => {
+            eprintln!("Usage: towpow balance <config.ron> [--write]");
+            process::exit(2);
+        }
+    };
+    run_tool(|| balance::run(Path::new(config), write))
+}
+
+// `towpow validate` checks all the assets and configs, see `validate::run`
+fn run_validate(args: &[String]) -> ! {
+    if !args.is_empty() {
+        eprintln!("Usage: towpow validate");
+        process::exit(2);
+    }
+    run_tool(validate::run)
+}
+
+// Runs one of the commands that don't open the game, and exits with whether it worked
+fn run_tool(tool: impl FnOnce() -> anyhow::Result<()>) -> ! {
+    // Results go to stdout, so keep the logs down to what's worth knowing about
+    amethyst::start_logger(LoggerConfig {
+        level_filter: LevelFilter::Warn,
+        ..Default::default()
+    });
+
+    match tool() {
+        Ok(()) => process::exit(0),
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::resources;
+use log::LevelFilter;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub const USAGE: &str = "\
+Usage: towpow [options]
+       towpow headless <scenario.ron>
+       towpow balance <config.ron> [--write]
+       towpow validate
+
+Options:
+    --level <name>          Start a new run on assets/levels/<name>.ron, skipping the menu
+    --save <file>           Pick up the run saved in <file>, skipping the menu
+    --wave <number>         Start the run at this wave, counting from 1
+    --money <amount>        Start the run with this much money
+    --endless               Keep the run going with generated waves once the level's are over
+    --seed <number>         Seed for anything random in the run, like the endless waves
+    --window-size <WxH>     Size of the window, instead of the one in config/display.ron
+    --log-level <level>     One of off, error, warn, info, debug or trace
+    --no-audio              Don't play any sound
+    --no-splash             Go straight to the menu once loaded
+    --hot-reload            Pick up changes to prefabs, waves and config/gameplay.ron while running.
+                            Runs played with it can't be replayed exactly
+    --help                  Show this";
+
+/// What the game was started with, mostly so developers and testers can jump straight to the
+/// part they care about. Kept around as a resource for the states to pick up.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LaunchOptions {
+    pub level: Option<String>,
+    pub save: Option<PathBuf>,
+    pub wave: Option<usize>,
+    pub money: Option<u32>,
+    pub endless: bool,
+    // Picked at random for every run without it
+    pub seed: Option<u64>,
+    pub window_size: Option<(u32, u32)>,
+    pub log_level: Option<LevelFilter>,
+    pub no_audio: bool,
+    pub no_splash: bool,
+    pub hot_reload: bool,
+    pub help: bool,
+}
+
+impl LaunchOptions {
+    pub fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut options = LaunchOptions::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow::anyhow!("{} needs a value", arg))
+            };
+            match arg.as_str() {
+                "--level" => options.level = Some(value()?.clone()),
+                "--save" => options.save = Some(PathBuf::from(value()?)),
+                "--wave" => {
+                    let wave = parse_value(arg, value()?)?;
+                    anyhow::ensure!(wave > 0, "Waves are counted from 1");
+                    options.wave = Some(wave);
+                }
+                "--money" => options.money = Some(parse_value(arg, value()?)?),
+                "--endless" => options.endless = true,
+                "--seed" => options.seed = Some(parse_value(arg, value()?)?),
+                "--window-size" => options.window_size = Some(parse_size(value()?)?),
+                "--log-level" => options.log_level = Some(parse_value(arg, value()?)?),
+                "--no-audio" => options.no_audio = true,
+                "--no-splash" => options.no_splash = true,
+                "--hot-reload" => options.hot_reload = true,
+                "--help" | "-h" => options.help = true,
+                _ => anyhow::bail!("Unknown option {:?}", arg),
+            }
+        }
+        anyhow::ensure!(
+            options.level.is_none() || options.save.is_none(),
+            "--level and --save can't be used together, saves already say which level they're on"
+        );
+        anyhow::ensure!(
+            options.save.is_none() || (options.wave.is_none() && options.money.is_none()),
+            "--wave and --money only apply to new runs, saves pick up where they were left"
+        );
+        Ok(options)
+    }
+
+    // Makes sure the level asked for is there, and that it, or every level the menu could start
+    // the run on without one, has the wave asked for. Endless runs have as many waves as it takes
+    pub fn check_level(&self, levels_dir: &Path) -> anyhow::Result<()> {
+        if let Some(ref level) = self.level {
+            let path = levels_dir.join(format!("{}.ron", level));
+            anyhow::ensure!(path.is_file(), "There's no level {:?} at {:?}", level, path);
+        }
+        let wave = match self.wave {
+            Some(wave) if !self.endless => wave,
+            _ => return Ok(()),
+        };
+        let paths = match self.level {
+            Some(ref level) => vec![levels_dir.join(format!("{}.ron", level))],
+            None => fs::read_dir(levels_dir)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|e| e == "ron"))
+                .collect(),
+        };
+        for path in paths {
+            let waves = resources::load_level(&path)?.waves.total_waves();
+            anyhow::ensure!(
+                wave <= waves,
+                "There's no wave {} in {:?}, it only has {}",
+                wave,
+                path,
+                waves
+            );
+        }
+        Ok(())
+    }
+}
+
+fn parse_value<T: FromStr>(option: &str, value: &str) -> anyhow::Result<T> {
+    value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("{:?} isn't a valid value for {}", value, option))
+}
+
+// Sizes are given as e.g. 1280x720
+fn parse_size(value: &str) -> anyhow::Result<(u32, u32)> {
+    let size = value.split_once('x').and_then(|(width, height)| {
+        Some((width.parse::<u32>().ok()?, height.parse::<u32>().ok()?))
+    });
+    match size {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => anyhow::bail!("{:?} isn't a valid window size, try e.g. 1280x720", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<LaunchOptions> {
+        let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+        LaunchOptions::parse(&args)
+    }
+
+    #[test]
+    fn parses_every_option() {
+        let options = parse(&[
+            "--level",
+            "level1",
+            "--wave",
+            "3",
+            "--money",
+            "500",
+            "--endless",
+            "--seed",
+            "42",
+            "--window-size",
+            "1280x720",
+            "--log-level",
+            "debug",
+            "--no-audio",
+            "--no-splash",
+            "--hot-reload",
+        ])
+        .unwrap();
+        assert_eq!(
+            options,
+            LaunchOptions {
+                level: Some("level1".to_string()),
+                wave: Some(3),
+                money: Some(500),
+                endless: true,
+                seed: Some(42),
+                window_size: Some((1280, 720)),
+                log_level: Some(LevelFilter::Debug),
+                no_audio: true,
+                no_splash: true,
+                hot_reload: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_bad_options() {
+        assert!(parse(&["--wave"]).is_err());
+        assert!(parse(&["--wave", "0"]).is_err());
+        assert!(parse(&["--money", "lots"]).is_err());
+        assert!(parse(&["--window-size", "1280"]).is_err());
+        assert!(parse(&["--level", "level1", "--save", "save.json"]).is_err());
+        assert!(parse(&["--save", "save.json", "--wave", "2"]).is_err());
+        assert!(parse(&["--fullscreen"]).is_err());
+    }
+
+    #[test]
+    fn rejects_missing_levels_and_waves() {
+        let levels_dir = amethyst::utils::application_root_dir()
+            .unwrap()
+            .join("assets")
+            .join("levels");
+        let check = |args: &[&str]| parse(args).unwrap().check_level(&levels_dir);
+        assert!(check(&["--level", "level1", "--wave", "1"]).is_ok());
+        assert!(check(&["--level", "level1", "--wave", "99"]).is_err());
+        assert!(check(&["--wave", "99"]).is_err());
+        assert!(check(&["--level", "level1", "--wave", "99", "--endless"]).is_ok());
+        assert!(check(&["--level", "level1"]).is_ok());
+        assert!(check(&["--level", "missing"]).is_err());
+    }
+}
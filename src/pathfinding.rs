@@ -0,0 +1,86 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+/// A cell on a grid, as (x, y)
+pub type Cell = (usize, usize);
+
+// Finds the shortest path between `start` and `goal` on a 4-connected grid using A*. The returned
+// path includes both `start` and `goal`. The start cell is never checked for walkability, so
+// anything standing on a blocked cell can still find its way out.
+// If None is returned, there is no way to reach the goal from the start.
+pub fn find_path<F>(
+    width: usize,
+    height: usize,
+    start: Cell,
+    goal: Cell,
+    walkable: F,
+) -> Option<Vec<Cell>>
+where
+    F: Fn(Cell) -> bool,
+{
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<Cell, Cell> = HashMap::new();
+    let mut cost: HashMap<Cell, usize> = HashMap::new();
+
+    cost.insert(start, 0);
+    open.push(Reverse((manhattan(start, goal), start)));
+
+    while let Some(Reverse((_, current))) = open.pop() {
+        if current == goal {
+            let mut path = vec![current];
+            let mut cell = current;
+            while let Some(prev) = came_from.get(&cell) {
+                cell = *prev;
+                path.push(cell);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let current_cost = cost[&current];
+        for neighbour in neighbours(current, width, height) {
+            if !walkable(neighbour) {
+                continue;
+            }
+
+            let neighbour_cost = current_cost + 1;
+            let improved = match cost.get(&neighbour) {
+                Some(c) => neighbour_cost < *c,
+                None => true,
+            };
+            if improved {
+                came_from.insert(neighbour, current);
+                cost.insert(neighbour, neighbour_cost);
+                open.push(Reverse((
+                    neighbour_cost + manhattan(neighbour, goal),
+                    neighbour,
+                )));
+            }
+        }
+    }
+
+    None
+}
+
+fn manhattan(a: Cell, b: Cell) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+fn neighbours((x, y): Cell, width: usize, height: usize) -> impl Iterator<Item = Cell> {
+    let mut cells = Vec::with_capacity(4);
+    if x > 0 {
+        cells.push((x - 1, y));
+    }
+    if x + 1 < width {
+        cells.push((x + 1, y));
+    }
+    if y > 0 {
+        cells.push((x, y - 1));
+    }
+    if y + 1 < height {
+        cells.push((x, y + 1));
+    }
+    cells.into_iter()
+}
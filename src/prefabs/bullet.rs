@@ -1,6 +1,9 @@
-use crate::components::Bullet;
+use crate::{
+    components::Bullet,
+    prefabs::{load_prefab, SpritePrefab},
+};
 use amethyst::{
-    assets::{Handle, Prefab, PrefabData, PrefabLoader, ProgressCounter, RonFormat},
+    assets::{Handle, Prefab, PrefabData, ProgressCounter},
     derive::PrefabData,
     ecs::prelude::Entity,
     error::Error,
@@ -15,12 +18,22 @@ pub struct BulletPrefab {
     bullet: Bullet,
 }
 
+impl BulletPrefab {
+    pub fn bullet_mut(&mut self) -> &mut Bullet {
+        &mut self.bullet
+    }
+}
+
+impl SpritePrefab for BulletPrefab {
+    fn sprite_scene_mut(&mut self) -> &mut SpriteScenePrefab {
+        &mut self.sprite_scene
+    }
+}
+
 pub fn load_bullet(
     world: &mut World,
     path: &str,
     progress_counter: &mut ProgressCounter,
 ) -> Handle<Prefab<BulletPrefab>> {
-    world.exec(|loader: PrefabLoader<'_, BulletPrefab>| {
-        loader.load(path, RonFormat, progress_counter)
-    })
+    load_prefab(world, path, progress_counter)
 }
@@ -1,6 +1,9 @@
-use crate::components::{Guided, Hitable, Velocity};
+use crate::{
+    components::{Bounty, Guided, Hitable, Velocity},
+    prefabs::{load_prefab, SpritePrefab},
+};
 use amethyst::{
-    assets::{Handle, Prefab, PrefabData, PrefabLoader, ProgressCounter, RonFormat},
+    assets::{Handle, Prefab, PrefabData, ProgressCounter},
     derive::PrefabData,
     ecs::prelude::Entity,
     error::Error,
@@ -15,6 +18,23 @@ pub struct EnemyPrefab {
     velocity: Velocity,
     guided: Guided,
     hitable: Hitable,
+    bounty: Bounty,
+}
+
+impl EnemyPrefab {
+    pub fn hitable(&self) -> &Hitable {
+        &self.hitable
+    }
+
+    pub fn hitable_mut(&mut self) -> &mut Hitable {
+        &mut self.hitable
+    }
+}
+
+impl SpritePrefab for EnemyPrefab {
+    fn sprite_scene_mut(&mut self) -> &mut SpriteScenePrefab {
+        &mut self.sprite_scene
+    }
 }
 
 pub fn load_enemy(
@@ -22,7 +42,5 @@ pub fn load_enemy(
     path: &str,
     progress_counter: &mut ProgressCounter,
 ) -> Handle<Prefab<EnemyPrefab>> {
-    world.exec(|loader: PrefabLoader<'_, EnemyPrefab>| {
-        loader.load(path, RonFormat, progress_counter)
-    })
+    load_prefab(world, path, progress_counter)
 }
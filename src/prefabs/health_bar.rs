@@ -0,0 +1,24 @@
+use amethyst::{
+    assets::{Handle, Prefab, PrefabData, PrefabLoader, ProgressCounter, RonFormat},
+    derive::PrefabData,
+    ecs::prelude::Entity,
+    error::Error,
+    prelude::World,
+    renderer::sprite::prefab::SpriteScenePrefab,
+};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize, PrefabData)]
+pub struct HealthBarPrefab {
+    sprite_scene: SpriteScenePrefab,
+}
+
+pub fn load_health_bar(
+    world: &mut World,
+    path: &str,
+    progress_counter: &mut ProgressCounter,
+) -> Handle<Prefab<HealthBarPrefab>> {
+    world.exec(|loader: PrefabLoader<'_, HealthBarPrefab>| {
+        loader.load(path, RonFormat, progress_counter)
+    })
+}
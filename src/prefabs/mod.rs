@@ -1,15 +1,21 @@
 mod background;
 mod bullet;
 mod enemy;
+mod health_bar;
 mod player;
 mod splash;
+mod spriteless;
 mod tower;
+mod wall;
 
 pub use self::{
     background::{load_background, BackgroundPrefab},
     bullet::{load_bullet, BulletPrefab},
     enemy::{load_enemy, EnemyPrefab},
+    health_bar::{load_health_bar, HealthBarPrefab},
     player::{load_player, PlayerPrefab},
     splash::{load_splash_screen, SplashAnimationPrefab},
+    spriteless::{load_prefab, Headless, SpritePrefab},
     tower::{load_tower, TowerPrefab},
+    wall::{load_wall, WallPrefab},
 };
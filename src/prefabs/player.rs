@@ -1,6 +1,9 @@
-use crate::components::{ControlledCharacter, Velocity};
+use crate::{
+    components::{ControlledCharacter, Velocity},
+    prefabs::{load_prefab, SpritePrefab},
+};
 use amethyst::{
-    assets::{Handle, Prefab, PrefabData, PrefabLoader, ProgressCounter, RonFormat},
+    assets::{Handle, Prefab, PrefabData, ProgressCounter},
     derive::PrefabData,
     ecs::prelude::Entity,
     error::Error,
@@ -16,11 +19,15 @@ pub struct PlayerPrefab {
     controlled_character: ControlledCharacter,
 }
 
+impl SpritePrefab for PlayerPrefab {
+    fn sprite_scene_mut(&mut self) -> &mut SpriteScenePrefab {
+        &mut self.sprite_scene
+    }
+}
+
 pub fn load_player(
     world: &mut World,
     progress_counter: &mut ProgressCounter,
 ) -> Handle<Prefab<PlayerPrefab>> {
-    world.exec(|loader: PrefabLoader<'_, PlayerPrefab>| {
-        loader.load("prefabs/player.ron", RonFormat, progress_counter)
-    })
+    load_prefab(world, "prefabs/player.ron", progress_counter)
 }
@@ -0,0 +1,60 @@
+use amethyst::{
+    assets::{Format, Handle, Prefab, PrefabLoader, ProgressCounter, RonFormat},
+    error::Error,
+    prelude::World,
+    renderer::sprite::prefab::SpriteScenePrefab,
+};
+use serde::Deserialize;
+
+/// Marks a world without a renderer. Prefabs loaded into it leave their sprites out, as there's
+/// nothing around to process the textures and they'd never finish loading.
+#[derive(Default)]
+pub struct Headless;
+
+pub trait SpritePrefab {
+    fn sprite_scene_mut(&mut self) -> &mut SpriteScenePrefab;
+}
+
+// Same as `RonFormat`, but drops the sprite sheet and sprite render from every entity
+#[derive(Clone, Debug, Default)]
+struct SpritelessFormat;
+
+impl<T> Format<Prefab<T>> for SpritelessFormat
+where
+    T: SpritePrefab + for<'a> Deserialize<'a> + Send + Sync + 'static,
+{
+    fn name(&self) -> &'static str {
+        "SpritelessRon"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<Prefab<T>, Error> {
+        let mut prefab: Prefab<T> = RonFormat.import_simple(bytes)?;
+        for index in 0..prefab.len() {
+            if let Some(data) = prefab.entity(index).and_then(|e| e.data_mut()) {
+                let sprite_scene = data.sprite_scene_mut();
+                sprite_scene.sheet = None;
+                sprite_scene.render = None;
+            }
+        }
+        Ok(prefab)
+    }
+}
+
+// Loads a prefab with or without its sprites, depending on whether the world is `Headless`
+pub fn load_prefab<T>(
+    world: &mut World,
+    path: &str,
+    progress_counter: &mut ProgressCounter,
+) -> Handle<Prefab<T>>
+where
+    T: SpritePrefab + for<'a> Deserialize<'a> + Send + Sync + 'static,
+{
+    let headless = world.has_value::<Headless>();
+    world.exec(|loader: PrefabLoader<'_, T>| {
+        if headless {
+            loader.load(path, SpritelessFormat, progress_counter)
+        } else {
+            loader.load(path, RonFormat, progress_counter)
+        }
+    })
+}
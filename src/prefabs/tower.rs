@@ -1,6 +1,9 @@
-use crate::components::Tower;
+use crate::{
+    components::Tower,
+    prefabs::{load_prefab, SpritePrefab},
+};
 use amethyst::{
-    assets::{Handle, Prefab, PrefabData, PrefabLoader, ProgressCounter, RonFormat},
+    assets::{Handle, Prefab, PrefabData, ProgressCounter},
     derive::PrefabData,
     ecs::prelude::Entity,
     error::Error,
@@ -15,12 +18,16 @@ pub struct TowerPrefab {
     tower: Tower,
 }
 
+impl SpritePrefab for TowerPrefab {
+    fn sprite_scene_mut(&mut self) -> &mut SpriteScenePrefab {
+        &mut self.sprite_scene
+    }
+}
+
 pub fn load_tower(
     world: &mut World,
     path: &str,
     progress_counter: &mut ProgressCounter,
 ) -> Handle<Prefab<TowerPrefab>> {
-    world.exec(|loader: PrefabLoader<'_, TowerPrefab>| {
-        loader.load(path, RonFormat, progress_counter)
-    })
+    load_prefab(world, path, progress_counter)
 }
@@ -0,0 +1,29 @@
+use crate::prefabs::{load_prefab, SpritePrefab};
+use amethyst::{
+    assets::{Handle, Prefab, PrefabData, ProgressCounter},
+    derive::PrefabData,
+    ecs::prelude::Entity,
+    error::Error,
+    prelude::World,
+    renderer::sprite::prefab::SpriteScenePrefab,
+};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize, PrefabData)]
+pub struct WallPrefab {
+    sprite_scene: SpriteScenePrefab,
+}
+
+impl SpritePrefab for WallPrefab {
+    fn sprite_scene_mut(&mut self) -> &mut SpriteScenePrefab {
+        &mut self.sprite_scene
+    }
+}
+
+pub fn load_wall(
+    world: &mut World,
+    path: &str,
+    progress_counter: &mut ProgressCounter,
+) -> Handle<Prefab<WallPrefab>> {
+    load_prefab(world, path, progress_counter)
+}
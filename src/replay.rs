@@ -0,0 +1,110 @@
+use crate::input::TickInput;
+use amethyst::utils::application_root_dir;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// Bump this whenever the format, or anything that changes how a run plays out, changes in a way
+// older replays can't be played back with
+const REPLAY_VERSION: u32 = 1;
+
+/// The input for every tick of a run, which is all it takes to play it again exactly as it went
+/// given that the simulation is deterministic.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Replay {
+    version: u32,
+    pub level: String,
+    // Seed the run was played with. Anything random comes from it, so it's as much a part of how
+    // the run went as the input
+    pub seed: u64,
+    pub endless: bool,
+    // Runs of identical ticks, as (count, input). Most ticks are the same as the one before
+    ticks: Vec<(u32, TickInput)>,
+    // Where playback is at, as (run, ticks played from that run)
+    #[serde(skip)]
+    cursor: (usize, u32),
+}
+
+impl Replay {
+    pub fn new(level: String, seed: u64) -> Self {
+        Replay {
+            version: REPLAY_VERSION,
+            level,
+            seed,
+            endless: false,
+            ticks: vec![],
+            cursor: (0, 0),
+        }
+    }
+
+    pub fn record(&mut self, input: &TickInput) {
+        match self.ticks.last_mut() {
+            Some((count, last)) if last == input => *count += 1,
+            _ => self.ticks.push((1, input.clone())),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    // Input for the next tick to play, or None once the replay is over
+    pub fn next_input(&mut self) -> Option<TickInput> {
+        let (run, played) = self.cursor;
+        let (count, input) = self.ticks.get(run)?;
+        self.cursor = if played + 1 < *count {
+            (run, played + 1)
+        } else {
+            (run + 1, 0)
+        };
+        Some(input.clone())
+    }
+}
+
+fn replay_path() -> anyhow::Result<PathBuf> {
+    Ok(application_root_dir()?
+        .join("replays")
+        .join("last_run.json"))
+}
+
+/// Replaces the last run's replay.
+pub fn write(replay: &Replay) -> anyhow::Result<()> {
+    let path = replay_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string(replay)?)
+        .map_err(|e| anyhow::anyhow!("Failed to write replay {:?}: {}", path, e))
+}
+
+/// Reads the last run's replay, if there is one.
+pub fn read() -> anyhow::Result<Option<Replay>> {
+    let path = replay_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    load(&path).map(Some)
+}
+
+pub fn load(path: &Path) -> anyhow::Result<Replay> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read replay {:?}: {}", path, e))?;
+    // Same as saves, check the version on its own so that old replays get a clear error
+    #[derive(Deserialize)]
+    struct Versioned {
+        version: u32,
+    }
+    let Versioned { version } = serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("Failed to read replay {:?}: {}", path, e))?;
+    anyhow::ensure!(
+        version == REPLAY_VERSION,
+        "Replay {:?} is version {}, but only version {} is supported",
+        path,
+        version,
+        REPLAY_VERSION
+    );
+    serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("Failed to read replay {:?}: {}", path, e))
+}
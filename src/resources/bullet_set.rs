@@ -22,21 +22,31 @@ pub struct BulletPrefabSet {
 }
 
 impl BulletPrefabSet {
+    // This array is only here to ensure that any time new item types are added, the match in
+    // `prefab_path` will cause a compiler error, and this array should get caught more easily
+    const TYPES: [BulletType; 1] = [BulletType::Standard];
+
+    // File the type's prefab is loaded from, relative to the assets directory
+    fn prefab_path(bullet_type: BulletType) -> &'static str {
+        // Remember to also update the array above!
+        match bullet_type {
+            BulletType::Standard => "prefabs/bullet.ron",
+        }
+    }
+
+    // Files all the prefabs are loaded from, for tools that edit them
+    pub fn prefab_paths() -> impl Iterator<Item = &'static str> {
+        Self::TYPES
+            .iter()
+            .map(|bullet_type| Self::prefab_path(*bullet_type))
+    }
+
     pub fn new(world: &mut World, progress_counter: &mut ProgressCounter) -> Self {
-        // This iterator is only here to ensure that any time new item types are added, the match
-        // will cause a compiler error, and this array should get caught more easily
-        let prefab_handles = [BulletType::Standard]
+        let prefab_handles = Self::TYPES
             .iter()
             .map(|bullet_type| {
-                (
-                    *bullet_type,
-                    // Remember to also update the array above!
-                    match bullet_type {
-                        BulletType::Standard => {
-                            load_bullet(world, "prefabs/bullet.ron", progress_counter)
-                        }
-                    },
-                )
+                let path = Self::prefab_path(*bullet_type);
+                (*bullet_type, load_bullet(world, path, progress_counter))
             })
             .collect();
 
@@ -56,6 +66,10 @@ impl BulletPrefabSet {
             .clone())
     }
 
+    pub fn handles(&self) -> impl Iterator<Item = &Handle<Prefab<BulletPrefab>>> {
+        self.prefab_handles.values()
+    }
+
     pub fn add_bullet<'s>(
         &self,
         bullet_type: BulletType,
@@ -72,7 +86,7 @@ impl BulletPrefabSet {
             .with(bullet_prefab, bullet_prefabs)
             .with(
                 Transform::new(
-                    Translation3::new(position.x, position.y + 10., 0.2),
+                    Translation3::new(position.x, position.y, 0.2),
                     UnitQuaternion::from_axis_angle(
                         &Unit::new_normalize(Vector3::new(0.0, 0.0, 1.0)),
                         dir.y.atan2(dir.x),
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Seconds of game time simulated on every tick
+pub const SIMULATION_STEP: f32 = 1. / 60.;
+
+// The only source of time gameplay systems should use. They run once per tick, always stepping by
+// the same amount no matter the frame rate
+#[derive(Clone, Copy, Debug)]
+pub struct SimulationClock {
+    pub step: f32,
+    pub tick: u64,
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        SimulationClock {
+            step: SIMULATION_STEP,
+            tick: 0,
+        }
+    }
+}
+
+// How fast the game runs, as picked by the player. Faster speeds run more ticks per frame rather
+// than bigger ones, so a run plays out the same at any speed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum GameSpeed {
+    Half,
+    #[default]
+    Normal,
+    Double,
+    Triple,
+}
+
+impl GameSpeed {
+    pub fn factor(self) -> f32 {
+        match self {
+            GameSpeed::Half => 0.5,
+            GameSpeed::Normal => 1.,
+            GameSpeed::Double => 2.,
+            GameSpeed::Triple => 3.,
+        }
+    }
+
+    // Goes through the speeds from slowest to fastest, and back around
+    pub fn next(self) -> Self {
+        match self {
+            GameSpeed::Half => GameSpeed::Normal,
+            GameSpeed::Normal => GameSpeed::Double,
+            GameSpeed::Double => GameSpeed::Triple,
+            GameSpeed::Triple => GameSpeed::Half,
+        }
+    }
+}
+
+impl fmt::Display for GameSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x", self.factor())
+    }
+}
+
+// Works out how many ticks to run on each of the game's fixed updates, at whatever speed it's at.
+// Slower speeds skip some updates, faster ones run a few ticks on each
+#[derive(Debug, Default)]
+pub struct TickPacer {
+    // Ticks owed from earlier updates, always less than one
+    owed: f32,
+}
+
+impl TickPacer {
+    pub fn ticks(&mut self, speed: GameSpeed) -> u32 {
+        self.owed += speed.factor();
+        let ticks = self.owed.floor();
+        self.owed -= ticks;
+        ticks as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_ticks_at_the_game_speed() {
+        let mut pacer = TickPacer::default();
+        let mut run = |speed, updates| (0..updates).map(|_| pacer.ticks(speed)).sum::<u32>();
+        assert_eq!(run(GameSpeed::Half, 4), 2);
+        assert_eq!(run(GameSpeed::Normal, 4), 4);
+        assert_eq!(run(GameSpeed::Triple, 4), 12);
+    }
+}
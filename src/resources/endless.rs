@@ -0,0 +1,249 @@
+use crate::resources::{rng::PHI, EnemyType, Wave, WaveGroup, WaveSchedule};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+
+/// How endless waves get harder, part of the gameplay config. Waves are counted from the first
+/// endless one, which comes right after the level's own.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EndlessCurve {
+    // Seconds between the end of a wave and the start of the next
+    pub delay: f32,
+    // Enemies in the first endless wave
+    pub count: usize,
+    // How much each wave grows on the one before, e.g. 0.1 for 10% more enemies every wave
+    pub count_growth: f32,
+    // Most enemies a wave can have. Past it, waves get harder through health alone, so late
+    // waves don't flood the map
+    pub max_count: usize,
+    pub health_growth: f32,
+    // Seconds between enemies, across the whole wave
+    pub interval: f32,
+    // Which enemies make up the waves
+    pub enemies: Vec<EndlessEnemy>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EndlessEnemy {
+    pub enemy: EnemyType,
+    // How likely each enemy of a wave is to be of this type, relative to the others
+    pub weight: f32,
+    // First endless wave it shows up in
+    #[serde(default = "first_wave")]
+    pub from_wave: usize,
+}
+
+fn first_wave() -> usize {
+    1
+}
+
+impl Default for EndlessCurve {
+    fn default() -> Self {
+        EndlessCurve {
+            delay: 10.,
+            count: 20,
+            count_growth: 0.1,
+            max_count: 80,
+            health_growth: 0.1,
+            interval: 0.8,
+            enemies: vec![EndlessEnemy {
+                enemy: EnemyType::Standard,
+                weight: 1.,
+                from_wave: 1,
+            }],
+        }
+    }
+}
+
+impl EndlessCurve {
+    pub(super) fn validate(&self, problems: &mut Vec<String>) {
+        if self.count == 0 {
+            problems.push("`endless.count` has to be more than 0".to_string());
+        }
+        if self.max_count < self.count {
+            problems.push("`endless.max_count` can't be less than `endless.count`".to_string());
+        }
+        if !self
+            .enemies
+            .iter()
+            .any(|e| e.from_wave <= 1 && e.weight > 0.)
+        {
+            problems.push(
+                "`endless.enemies` needs an enemy with some weight from the first wave".to_string(),
+            );
+        }
+        if self
+            .enemies
+            .iter()
+            .any(|e| !e.weight.is_finite() || e.weight < 0.)
+        {
+            problems.push("`endless.enemies` can't have negative weights".to_string());
+        }
+    }
+}
+
+/// Present while the run keeps going once the level's waves run out. Every endless wave only
+/// depends on the seed and its number, so saves and replays get the exact same ones. The seed is
+/// the run's endless waves stream, see `GameRng`.
+#[derive(Clone, Copy, Debug)]
+pub struct EndlessWaves {
+    pub seed: u64,
+    // Waves the level itself has, which come before the endless ones
+    authored: usize,
+}
+
+impl EndlessWaves {
+    pub fn new(seed: u64, schedule: &WaveSchedule) -> Self {
+        EndlessWaves {
+            seed,
+            authored: schedule.total_waves(),
+        }
+    }
+
+    // Adds endless waves to the schedule until it has at least `total` of them
+    pub fn fill(&self, schedule: &mut WaveSchedule, curve: &EndlessCurve, total: usize) {
+        // Enemies go along the same routes as the level's own waves, which are known to work
+        let mut routes: Vec<(String, String)> = schedule
+            .waves()
+            .iter()
+            .take(self.authored)
+            .flat_map(|wave| wave.groups.iter())
+            .map(|group| (group.spawn.clone(), group.exit.clone()))
+            .collect();
+        routes.sort_unstable();
+        routes.dedup();
+        if routes.is_empty() {
+            return;
+        }
+
+        while schedule.total_waves() < total {
+            let number = schedule.total_waves() + 1 - self.authored;
+            schedule.push(self.generate(number, curve, &routes));
+        }
+    }
+
+    // Endless wave `number`, counting from 1
+    fn generate(&self, number: usize, curve: &EndlessCurve, routes: &[(String, String)]) -> Wave {
+        let mut rng = Pcg64Mcg::seed_from_u64(self.seed ^ (number as u64).wrapping_mul(PHI));
+        let growth = |rate: f32| (1. + rate).powi(number as i32 - 1);
+        let uncapped = (curve.count as f32 * growth(curve.count_growth)).round() as usize;
+        let count = uncapped.min(curve.max_count);
+        // The enemies that didn't make it into the wave make up for it in health
+        let health = growth(curve.health_growth) * uncapped as f32 / count as f32;
+        let enemies: Vec<&EndlessEnemy> = curve
+            .enemies
+            .iter()
+            .filter(|e| e.from_wave <= number && e.weight > 0.)
+            .collect();
+        let total_weight: f32 = enemies.iter().map(|e| e.weight).sum();
+
+        // Enemies of the same type going the same way make up a group
+        let mut groups: Vec<WaveGroup> = vec![];
+        for _ in 0..count {
+            let mut pick = rng.gen_range(0., total_weight);
+            let enemy = enemies
+                .iter()
+                .find(|e| {
+                    pick -= e.weight;
+                    pick < 0.
+                })
+                .unwrap_or(&enemies[enemies.len() - 1])
+                .enemy;
+            let (spawn, exit) = &routes[rng.gen_range(0, routes.len())];
+            match groups
+                .iter_mut()
+                .find(|g| g.enemy == enemy && g.spawn == *spawn && g.exit == *exit)
+            {
+                Some(group) => group.count += 1,
+                None => groups.push(WaveGroup {
+                    enemy,
+                    count: 1,
+                    interval: None,
+                    health: Some(health),
+                    spawn: spawn.clone(),
+                    exit: exit.clone(),
+                }),
+            }
+        }
+        // Groups spawn side by side, so they each go slower to keep to the curve's interval
+        let interval = curve.interval * groups.len() as f32;
+        for group in &mut groups {
+            group.interval = Some(interval);
+        }
+
+        Wave {
+            delay: curve.delay,
+            groups,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> WaveSchedule {
+        WaveSchedule::new(vec![Wave {
+            delay: 1.,
+            groups: vec![WaveGroup {
+                enemy: EnemyType::Standard,
+                count: 5,
+                interval: None,
+                health: None,
+                spawn: "north".to_string(),
+                exit: "south".to_string(),
+            }],
+        }])
+    }
+
+    #[test]
+    fn waves_only_depend_on_the_seed() {
+        let curve = EndlessCurve::default();
+        let mut first = schedule();
+        let endless = EndlessWaves::new(7, &first);
+        endless.fill(&mut first, &curve, 4);
+        // Generating them one at a time, like the spawner does, makes no difference
+        let mut second = schedule();
+        for total in 2..=4 {
+            endless.fill(&mut second, &curve, total);
+        }
+
+        assert_eq!(first.total_waves(), 4);
+        assert_eq!(
+            format!("{:?}", first.waves()),
+            format!("{:?}", second.waves())
+        );
+    }
+
+    #[test]
+    fn waves_get_harder() {
+        let curve = EndlessCurve::default();
+        let mut schedule = schedule();
+        EndlessWaves::new(7, &schedule).fill(&mut schedule, &curve, 6);
+
+        let count = |wave: &Wave| wave.groups.iter().map(|g| g.count).sum::<usize>();
+        let health = |wave: &Wave| wave.groups[0].health.unwrap();
+        let endless = &schedule.waves()[1..];
+        assert_eq!(count(&endless[0]), curve.count);
+        assert!(endless
+            .windows(2)
+            .all(|w| count(&w[1]) >= count(&w[0]) && health(&w[1]) > health(&w[0])));
+    }
+
+    #[test]
+    fn waves_stop_growing_past_the_max_count() {
+        let curve = EndlessCurve {
+            max_count: 30,
+            ..Default::default()
+        };
+        let mut schedule = schedule();
+        EndlessWaves::new(7, &schedule).fill(&mut schedule, &curve, 41);
+
+        // Over ten times as many enemies as the cap by then, which go into health instead
+        let last = &schedule.waves()[40];
+        assert_eq!(last.groups.iter().map(|g| g.count).sum::<usize>(), 30);
+        assert!(last.groups[0].health.unwrap() > 1.1f32.powi(39) * 10.);
+    }
+}
@@ -1,15 +1,19 @@
-use crate::prefabs::{load_enemy, EnemyPrefab};
+use crate::{
+    components::{Destination, EnemyKind},
+    pathfinding::Cell,
+    prefabs::{load_enemy, EnemyPrefab},
+};
 use amethyst::{
     assets::{Handle, Prefab, ProgressCounter},
-    ecs::{Entities, WriteStorage},
+    core::Transform,
+    ecs::{Entities, Entity, WriteStorage},
     prelude::World,
 };
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Default)]
-pub struct EnemySpawning;
-
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
 pub enum EnemyType {
     Standard,
 }
@@ -20,21 +24,31 @@ pub struct EnemyPrefabSet {
 }
 
 impl EnemyPrefabSet {
+    // This array is only here to ensure that any time new item types are added, the match in
+    // `prefab_path` will cause a compiler error, and this array should get caught more easily
+    const TYPES: [EnemyType; 1] = [EnemyType::Standard];
+
+    // File the type's prefab is loaded from, relative to the assets directory
+    fn prefab_path(enemy_type: EnemyType) -> &'static str {
+        // Remember to also update the array above!
+        match enemy_type {
+            EnemyType::Standard => "prefabs/enemy.ron",
+        }
+    }
+
+    // Files all the prefabs are loaded from, for tools that edit them
+    pub fn prefab_paths() -> impl Iterator<Item = &'static str> {
+        Self::TYPES
+            .iter()
+            .map(|enemy_type| Self::prefab_path(*enemy_type))
+    }
+
     pub fn new(world: &mut World, progress_counter: &mut ProgressCounter) -> Self {
-        // This iterator is only here to ensure that any time new item types are added, the match
-        // will cause a compiler error, and this array should get caught more easily
-        let prefab_handles = [EnemyType::Standard]
+        let prefab_handles = Self::TYPES
             .iter()
             .map(|enemy_type| {
-                (
-                    *enemy_type,
-                    // Remember to also update the array above!
-                    match enemy_type {
-                        EnemyType::Standard => {
-                            load_enemy(world, "prefabs/enemy.ron", progress_counter)
-                        }
-                    },
-                )
+                let path = Self::prefab_path(*enemy_type);
+                (*enemy_type, load_enemy(world, path, progress_counter))
             })
             .collect();
 
@@ -51,18 +65,31 @@ impl EnemyPrefabSet {
             .clone())
     }
 
+    pub fn handles(&self) -> impl Iterator<Item = &Handle<Prefab<EnemyPrefab>>> {
+        self.prefab_handles.values()
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn add_enemy<'s>(
         &self,
         enemy_type: EnemyType,
+        position: Point2<f32>,
+        exit: Cell,
         entities: &Entities<'s>,
         enemy_prefabs: &mut WriteStorage<'s, Handle<Prefab<EnemyPrefab>>>,
-    ) -> anyhow::Result<()> {
+        transforms: &mut WriteStorage<'s, Transform>,
+        destinations: &mut WriteStorage<'s, Destination>,
+        enemy_kinds: &mut WriteStorage<'s, EnemyKind>,
+    ) -> anyhow::Result<Entity> {
         let enemy_prefab = self.get_handle(enemy_type)?;
-        entities
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(position.x, position.y, 0.5);
+        Ok(entities
             .build_entity()
             .with(enemy_prefab, enemy_prefabs)
-            .build();
-
-        Ok(())
+            .with(transform, transforms)
+            .with(Destination { exit }, destinations)
+            .with(EnemyKind(enemy_type), enemy_kinds)
+            .build())
     }
 }
@@ -0,0 +1,115 @@
+use crate::resources::EndlessCurve;
+use amethyst::config::Config;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Numbers the gameplay systems go by, read from config/gameplay.ron. Anything left out of the
+/// file keeps its default.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameplayConfig {
+    // How close the player has to be to a free tower to get in
+    pub tower_enter_radius: f32,
+    // How close to their tower the player has to be to get out. Getting in puts them right on top
+    // of it, so this can be tiny
+    pub tower_exit_radius: f32,
+    // Bullets further than this from the centre of the map are gone for good
+    pub bullet_despawn_radius: f32,
+    // How far above the tower's centre bullets come out
+    pub bullet_spawn_offset: f32,
+    // Seconds between enemies of a wave group that doesn't give its own interval
+    pub spawn_interval: f32,
+    // How far the camera lets the player get from the centre of the screen, as (x, y), before it
+    // follows
+    pub camera_dead_zone: (f32, f32),
+    // Money for every second a wave is called early, rounded down
+    pub early_call_bonus: f32,
+    // Money it takes to build each thing
+    pub tower_cost: u32,
+    pub wall_cost: u32,
+    pub endless: EndlessCurve,
+}
+
+impl Default for GameplayConfig {
+    fn default() -> Self {
+        GameplayConfig {
+            tower_enter_radius: 60.,
+            tower_exit_radius: 1.,
+            bullet_despawn_radius: 1000.,
+            bullet_spawn_offset: 10.,
+            spawn_interval: 2.,
+            camera_dead_zone: (120., 68.),
+            early_call_bonus: 5.,
+            tower_cost: 50,
+            wall_cost: 5,
+            endless: EndlessCurve::default(),
+        }
+    }
+}
+
+impl GameplayConfig {
+    // Lists everything wrong with the values at once, rather than stopping at the first
+    fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let mut check = |name: &str, value: f32, allow_zero: bool| {
+            if !value.is_finite() || value < 0. || (value == 0. && !allow_zero) {
+                problems.push(format!(
+                    "`{}` is {}, but has to be {}",
+                    name,
+                    value,
+                    if allow_zero {
+                        "0 or more"
+                    } else {
+                        "more than 0"
+                    }
+                ));
+            }
+        };
+        check("tower_enter_radius", self.tower_enter_radius, false);
+        check("tower_exit_radius", self.tower_exit_radius, false);
+        check("bullet_despawn_radius", self.bullet_despawn_radius, false);
+        check("bullet_spawn_offset", self.bullet_spawn_offset, true);
+        check("spawn_interval", self.spawn_interval, false);
+        check("camera_dead_zone.0", self.camera_dead_zone.0, true);
+        check("camera_dead_zone.1", self.camera_dead_zone.1, true);
+        check("early_call_bonus", self.early_call_bonus, true);
+        check("endless.delay", self.endless.delay, true);
+        check("endless.count_growth", self.endless.count_growth, true);
+        check("endless.health_growth", self.endless.health_growth, true);
+        check("endless.interval", self.endless.interval, false);
+        self.endless.validate(&mut problems);
+        problems
+    }
+}
+
+pub fn load_gameplay_config<P: AsRef<Path>>(path: P) -> anyhow::Result<GameplayConfig> {
+    let path = path.as_ref();
+    let config = GameplayConfig::load(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read gameplay config {:?}: {}", path, e))?;
+    let problems = config.validate();
+    anyhow::ensure!(
+        problems.is_empty(),
+        "Invalid gameplay config {:?}:\n    {}",
+        path,
+        problems.join("\n    ")
+    );
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_every_problem() {
+        let config = GameplayConfig {
+            tower_enter_radius: 0.,
+            spawn_interval: -2.,
+            ..Default::default()
+        };
+        let problems = config.validate();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("tower_enter_radius"));
+        assert!(problems[1].contains("spawn_interval"));
+    }
+}
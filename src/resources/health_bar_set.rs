@@ -0,0 +1,62 @@
+use crate::{
+    components::HealthBar,
+    prefabs::{load_health_bar, HealthBarPrefab},
+};
+use amethyst::{
+    assets::{Handle, Prefab, ProgressCounter},
+    core::{Parent, Transform},
+    ecs::{Entities, Entity, WriteStorage},
+    prelude::World,
+};
+
+// Width of the health bar sprite, in world units
+pub const HEALTH_BAR_WIDTH: f32 = 24.;
+
+#[derive(Default)]
+pub struct HealthBarPrefabSet {
+    prefab_handle: Option<Handle<Prefab<HealthBarPrefab>>>,
+}
+
+impl HealthBarPrefabSet {
+    pub fn new(world: &mut World, progress_counter: &mut ProgressCounter) -> Self {
+        HealthBarPrefabSet {
+            prefab_handle: Some(load_health_bar(
+                world,
+                "prefabs/health_bar.ron",
+                progress_counter,
+            )),
+        }
+    }
+
+    pub fn get_handle(&self) -> anyhow::Result<Handle<Prefab<HealthBarPrefab>>> {
+        Ok(self
+            .prefab_handle
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Prefab for health bars was not loaded."))?
+            .clone())
+    }
+
+    pub fn add_health_bar<'s>(
+        &self,
+        owner: Entity,
+        entities: &Entities<'s>,
+        health_bar_prefabs: &mut WriteStorage<'s, Handle<Prefab<HealthBarPrefab>>>,
+        health_bars: &mut WriteStorage<'s, HealthBar>,
+        parents: &mut WriteStorage<'s, Parent>,
+        transforms: &mut WriteStorage<'s, Transform>,
+    ) -> anyhow::Result<()> {
+        let health_bar_prefab = self.get_handle()?;
+        // The transform is relative to the owner, so this sits right above it
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(0., 20., 0.1);
+        entities
+            .build_entity()
+            .with(health_bar_prefab, health_bar_prefabs)
+            .with(HealthBar { owner }, health_bars)
+            .with(Parent::new(owner), parents)
+            .with(transform, transforms)
+            .build();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{
+    pathfinding::{self, Cell},
+    resources::{Wave, WaveSchedule},
+};
+use amethyst::config::Config;
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct LevelDefinition {
+    cell_size: f32,
+    origin: (f32, f32),
+    // Rows are stored top to bottom, as they're written in the level file
+    rows: Vec<String>,
+    spawns: BTreeMap<String, Cell>,
+    exits: BTreeMap<String, Cell>,
+    waves: Vec<Wave>,
+    // Money and lives the player starts the level with
+    money: u32,
+    lives: u32,
+}
+
+pub struct Level {
+    pub map: LevelMap,
+    pub waves: WaveSchedule,
+    pub money: u32,
+    pub lives: u32,
+}
+
+pub struct LevelMap {
+    // Name of the level file, without the extension
+    name: String,
+    cell_size: f32,
+    origin: Point2<f32>,
+    width: usize,
+    height: usize,
+    // Indexed by y * width + x, with y = 0 being the bottom row
+    walkable: Vec<bool>,
+    spawns: BTreeMap<String, Cell>,
+    exits: BTreeMap<String, Cell>,
+    // Bumped every time the walkable cells change, so that paths can be recomputed
+    revision: u64,
+}
+
+// Loads both the map and the waves of enemies that will run through it
+pub fn load_level<P: AsRef<Path>>(path: P) -> anyhow::Result<Level> {
+    let path = path.as_ref();
+    let definition = LevelDefinition::load(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read level {:?}: {}", path, e))?;
+    let waves = definition.waves.clone();
+    let (money, lives) = (definition.money, definition.lives);
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Level path {:?} has no file name", path))?;
+    let level_map = LevelMap::from_definition(name, definition)?;
+    level_map.check_waves(&waves)?;
+
+    Ok(Level {
+        map: level_map,
+        waves: WaveSchedule::new(waves),
+        money,
+        lives,
+    })
+}
+
+// Loads waves from a file of their own, e.g. to run a level with different ones. They still have to
+// be checked against the level they're used with
+pub fn load_waves<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<Wave>> {
+    let path = path.as_ref();
+    Vec::<Wave>::load(path).map_err(|e| anyhow::anyhow!("Failed to read waves {:?}: {}", path, e))
+}
+
+impl LevelMap {
+    fn from_definition(name: &str, definition: LevelDefinition) -> anyhow::Result<Self> {
+        let height = definition.rows.len();
+        let width = definition.rows.first().map_or(0, |row| row.chars().count());
+        anyhow::ensure!(width > 0 && height > 0, "Level has no cells");
+
+        let mut walkable = vec![false; width * height];
+        // Flip the rows so that y grows upwards like the world does
+        for (y, row) in definition.rows.iter().rev().enumerate() {
+            anyhow::ensure!(
+                row.chars().count() == width,
+                "Level row {:?} does not have {} cells",
+                row,
+                width
+            );
+            for (x, c) in row.chars().enumerate() {
+                walkable[y * width + x] = match c {
+                    '.' => true,
+                    '#' => false,
+                    _ => anyhow::bail!("Unknown level cell {:?} in row {:?}", c, row),
+                };
+            }
+        }
+
+        let level = LevelMap {
+            name: name.to_string(),
+            cell_size: definition.cell_size,
+            origin: Point2::new(definition.origin.0, definition.origin.1),
+            width,
+            height,
+            walkable,
+            spawns: definition.spawns,
+            exits: definition.exits,
+            revision: 0,
+        };
+        anyhow::ensure!(!level.spawns.is_empty(), "Level has no spawns");
+        anyhow::ensure!(!level.exits.is_empty(), "Level has no exits");
+        for (name, cell) in level.spawns.iter().chain(level.exits.iter()) {
+            anyhow::ensure!(
+                level.is_walkable(*cell),
+                "{:?} at {:?} is not a walkable cell",
+                name,
+                cell
+            );
+        }
+
+        Ok(level)
+    }
+
+    // Makes sure every spawn and exit the waves go through is in the level
+    pub fn check_waves(&self, waves: &[Wave]) -> anyhow::Result<()> {
+        for wave in waves {
+            for group in &wave.groups {
+                anyhow::ensure!(
+                    self.spawns.contains_key(&group.spawn),
+                    "Wave group uses unknown spawn {:?}",
+                    group.spawn
+                );
+                anyhow::ensure!(
+                    self.exits.contains_key(&group.exit),
+                    "Wave group uses unknown exit {:?}",
+                    group.exit
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn spawns(&self) -> impl Iterator<Item = (&str, Cell)> {
+        self.spawns
+            .iter()
+            .map(|(name, cell)| (name.as_str(), *cell))
+    }
+
+    pub fn exits(&self) -> impl Iterator<Item = (&str, Cell)> {
+        self.exits.iter().map(|(name, cell)| (name.as_str(), *cell))
+    }
+
+    pub fn spawn(&self, name: &str) -> Option<Cell> {
+        self.spawns.get(name).copied()
+    }
+
+    pub fn exit(&self, name: &str) -> Option<Cell> {
+        self.exits.get(name).copied()
+    }
+
+    pub fn is_walkable(&self, (x, y): Cell) -> bool {
+        x < self.width && y < self.height && self.walkable[y * self.width + x]
+    }
+
+    pub fn set_walkable(&mut self, (x, y): Cell, walkable: bool) {
+        if x < self.width && y < self.height {
+            self.walkable[y * self.width + x] = walkable;
+            self.revision += 1;
+        }
+    }
+
+    // Checks whether the cell can be blocked off without sealing the path from any spawn to any
+    // exit, or from any of the occupied cells to their exit (i.e. cells with enemies in them, as
+    // (position, exit) pairs). Occupied cells can never be blocked.
+    pub fn can_block(&self, cell: Cell, occupied: &[(Cell, Cell)]) -> bool {
+        if !self.is_walkable(cell)
+            || self.spawns.values().any(|c| *c == cell)
+            || self.exits.values().any(|c| *c == cell)
+            || occupied.iter().any(|(c, _)| *c == cell)
+        {
+            return false;
+        }
+
+        let walkable = |c: Cell| c != cell && self.is_walkable(c);
+        let has_path = |start: Cell, goal: Cell| {
+            pathfinding::find_path(self.width, self.height, start, goal, walkable).is_some()
+        };
+        self.spawns
+            .values()
+            .all(|spawn| self.exits.values().all(|exit| has_path(*spawn, *exit)))
+            && occupied.iter().all(|(start, exit)| has_path(*start, *exit))
+    }
+
+    // Positions outside of the grid get clamped to the closest cell on the edge
+    pub fn world_to_cell(&self, position: Point2<f32>) -> Cell {
+        let relative = (position - self.origin) / self.cell_size;
+        let clamp = |v: f32, max: usize| (v.floor().max(0.) as usize).min(max - 1);
+        (
+            clamp(relative.x, self.width),
+            clamp(relative.y, self.height),
+        )
+    }
+
+    // Unlike `world_to_cell`, positions outside of the grid do not belong to any cell
+    pub fn cell_at(&self, position: Point2<f32>) -> Option<Cell> {
+        let relative = (position - self.origin) / self.cell_size;
+        if relative.x < 0. || relative.y < 0. {
+            return None;
+        }
+        let cell = (relative.x.floor() as usize, relative.y.floor() as usize);
+        if cell.0 < self.width && cell.1 < self.height {
+            Some(cell)
+        } else {
+            None
+        }
+    }
+
+    // Returns the world position of the center of the cell
+    pub fn cell_to_world(&self, (x, y): Cell) -> Point2<f32> {
+        Point2::new(
+            self.origin.x + (x as f32 + 0.5) * self.cell_size,
+            self.origin.y + (y as f32 + 0.5) * self.cell_size,
+        )
+    }
+
+    // Path from the given position to an exit, as world positions of each cell to go through.
+    // The cell the position is in is not included, unless it's the exit itself.
+    pub fn path_to(&self, position: Point2<f32>, exit: Cell) -> Option<Vec<Point2<f32>>> {
+        let start = self.world_to_cell(position);
+        let cells = pathfinding::find_path(self.width, self.height, start, exit, |cell| {
+            self.is_walkable(cell)
+        })?;
+        let skip = if cells.len() > 1 { 1 } else { 0 };
+        Some(
+            cells
+                .into_iter()
+                .skip(skip)
+                .map(|cell| self.cell_to_world(cell))
+                .collect(),
+        )
+    }
+}
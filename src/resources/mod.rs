@@ -1,11 +1,29 @@
 mod bullet_set;
 mod camera;
+mod clock;
+mod endless;
 mod enemy_set;
+mod gameplay;
+mod health_bar_set;
+mod level;
 mod placement;
+mod rng;
+mod status;
+mod wall_set;
+mod wave;
 
 pub use self::{
     bullet_set::{BulletPrefabSet, BulletType},
     camera::FollowedObject,
-    enemy_set::{EnemyPrefabSet, EnemySpawning, EnemyType},
-    placement::{TowerPlacement, TowerPrefabSet, TowerType},
+    clock::{GameSpeed, SimulationClock, TickPacer, SIMULATION_STEP},
+    endless::{EndlessCurve, EndlessWaves},
+    enemy_set::{EnemyPrefabSet, EnemyType},
+    gameplay::{load_gameplay_config, GameplayConfig},
+    health_bar_set::{HealthBarPrefabSet, HEALTH_BAR_WIDTH},
+    level::{load_level, load_waves, Level, LevelMap},
+    placement::{Buildable, TowerPlacement, TowerPrefabSet, TowerType},
+    rng::{GameRng, RngStream},
+    status::{Economy, Lives, RunStats, Score},
+    wall_set::WallPrefabSet,
+    wave::{Wave, WaveGroup, WaveProgress, WaveSchedule},
 };
@@ -1,43 +1,73 @@
-use crate::prefabs::{load_tower, TowerPrefab};
+use crate::{
+    components::TowerKind,
+    prefabs::{load_tower, TowerPrefab},
+    resources::GameplayConfig,
+};
 use amethyst::{
     assets::{Handle, Prefab, ProgressCounter},
     core::Transform,
-    ecs::{Entities, WriteStorage},
+    ecs::{Entities, Entity, WriteStorage},
     prelude::World,
 };
 use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 pub struct TowerPlacement {
-    pub placing: bool,
+    pub placing: Option<Buildable>,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
 pub enum TowerType {
     Standard,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+pub enum Buildable {
+    Tower(TowerType),
+    Wall,
+}
+
+impl Buildable {
+    pub fn cost(&self, gameplay: &GameplayConfig) -> u32 {
+        match self {
+            Buildable::Tower(TowerType::Standard) => gameplay.tower_cost,
+            Buildable::Wall => gameplay.wall_cost,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct TowerPrefabSet {
     prefab_handles: HashMap<TowerType, Handle<Prefab<TowerPrefab>>>,
 }
 
 impl TowerPrefabSet {
+    // This array is only here to ensure that any time new item types are added, the match in
+    // `prefab_path` will cause a compiler error, and this array should get caught more easily
+    const TYPES: [TowerType; 1] = [TowerType::Standard];
+
+    // File the type's prefab is loaded from, relative to the assets directory
+    fn prefab_path(tower_type: TowerType) -> &'static str {
+        // Remember to also update the array above!
+        match tower_type {
+            TowerType::Standard => "prefabs/tower.ron",
+        }
+    }
+
+    // Files all the prefabs are loaded from, for tools that edit them
+    pub fn prefab_paths() -> impl Iterator<Item = &'static str> {
+        Self::TYPES
+            .iter()
+            .map(|tower_type| Self::prefab_path(*tower_type))
+    }
+
     pub fn new(world: &mut World, progress_counter: &mut ProgressCounter) -> Self {
-        // This iterator is only here to ensure that any time new item types are added, the match
-        // will cause a compiler error, and this array should get caught more easily
-        let prefab_handles = [TowerType::Standard]
+        let prefab_handles = Self::TYPES
             .iter()
-            .map(|bullet_type| {
-                (
-                    *bullet_type,
-                    // Remember to also update the array above!
-                    match bullet_type {
-                        TowerType::Standard => {
-                            load_tower(world, "prefabs/tower.ron", progress_counter)
-                        }
-                    },
-                )
+            .map(|tower_type| {
+                let path = Self::prefab_path(*tower_type);
+                (*tower_type, load_tower(world, path, progress_counter))
             })
             .collect();
 
@@ -61,16 +91,16 @@ impl TowerPrefabSet {
         entities: &Entities<'s>,
         tower_prefabs: &mut WriteStorage<'s, Handle<Prefab<TowerPrefab>>>,
         transforms: &mut WriteStorage<'s, Transform>,
-    ) -> anyhow::Result<()> {
+        tower_kinds: &mut WriteStorage<'s, TowerKind>,
+    ) -> anyhow::Result<Entity> {
         let tower_prefab = self.get_handle(tower_type)?;
         let mut transform = Transform::default();
         transform.set_translation_xyz(position.x, position.y, 0.3);
-        entities
+        Ok(entities
             .build_entity()
             .with(tower_prefab, tower_prefabs)
             .with(transform, transforms)
-            .build();
-
-        Ok(())
+            .with(TowerKind(tower_type), tower_kinds)
+            .build())
     }
 }
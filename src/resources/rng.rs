@@ -0,0 +1,61 @@
+/// What a stream of random numbers is used for. Every use gets its own stream, so that drawing
+/// more numbers for one thing doesn't change what anything else gets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RngStream {
+    EndlessWaves,
+}
+
+impl RngStream {
+    // Mixed into the run's seed to get the stream's own. Never change these, or saves and replays
+    // would get different numbers than they were played with
+    fn salt(self) -> u64 {
+        match self {
+            RngStream::EndlessWaves => 1,
+        }
+    }
+}
+
+/// The one source of randomness for gameplay, seeded once per run. Anything random a system
+/// does has to come from here, or replays wouldn't play out the way the run did.
+#[derive(Clone, Copy, Debug)]
+pub struct GameRng {
+    seed: u64,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng { seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Seed of the stream on its own. Whatever uses the stream seeds its own generator with it, in
+    // a way that only depends on where the run is at, like the endless waves do with their number
+    pub fn stream_seed(&self, stream: RngStream) -> u64 {
+        mix(self.seed ^ stream.salt().wrapping_mul(PHI))
+    }
+}
+
+// Spreads consecutive numbers far apart, so that similar seeds don't give similar streams
+pub(super) const PHI: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// SplitMix64's finalizer, so that every bit of the seed affects every bit of the result
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_only_depend_on_the_seed() {
+        let stream = |seed| GameRng::new(seed).stream_seed(RngStream::EndlessWaves);
+        assert_eq!(stream(42), stream(42));
+        assert_ne!(stream(42), stream(43));
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+pub struct Economy {
+    pub money: u32,
+}
+
+impl Economy {
+    // Takes the cost out of the available money, as long as there's enough of it
+    pub fn spend(&mut self, cost: u32) -> bool {
+        if self.money >= cost {
+            self.money -= cost;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn earn(&mut self, amount: u32) {
+        self.money += amount;
+    }
+}
+
+pub struct Lives {
+    pub remaining: u32,
+}
+
+#[derive(Default)]
+pub struct Score {
+    pub points: u32,
+}
+
+// Everything that happened during a run, for the summary shown once it's over
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RunStats {
+    pub kills: u32,
+    pub towers_built: u32,
+    pub money_earned: u32,
+    // In seconds, not counting the time spent paused
+    pub time_played: f32,
+}
@@ -0,0 +1,62 @@
+use crate::{
+    components::Wall,
+    prefabs::{load_wall, WallPrefab},
+};
+use amethyst::{
+    assets::{Handle, Prefab, ProgressCounter},
+    core::Transform,
+    ecs::{Entities, WriteStorage},
+    prelude::World,
+};
+use nalgebra::Point2;
+
+#[derive(Default)]
+pub struct WallPrefabSet {
+    prefab_handle: Option<Handle<Prefab<WallPrefab>>>,
+}
+
+impl WallPrefabSet {
+    // File the prefab is loaded from, relative to the assets directory
+    const PREFAB_PATH: &'static str = "prefabs/wall.ron";
+
+    // Files all the prefabs are loaded from, for tools that check them. There's only the one
+    pub fn prefab_paths() -> impl Iterator<Item = &'static str> {
+        std::iter::once(Self::PREFAB_PATH)
+    }
+
+    pub fn new(world: &mut World, progress_counter: &mut ProgressCounter) -> Self {
+        WallPrefabSet {
+            prefab_handle: Some(load_wall(world, Self::PREFAB_PATH, progress_counter)),
+        }
+    }
+
+    pub fn get_handle(&self) -> anyhow::Result<Handle<Prefab<WallPrefab>>> {
+        Ok(self
+            .prefab_handle
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Prefab for walls was not loaded."))?
+            .clone())
+    }
+
+    // The position is expected to be the center of the cell
+    pub fn add_wall<'s>(
+        &self,
+        position: Point2<f32>,
+        entities: &Entities<'s>,
+        wall_prefabs: &mut WriteStorage<'s, Handle<Prefab<WallPrefab>>>,
+        walls: &mut WriteStorage<'s, Wall>,
+        transforms: &mut WriteStorage<'s, Transform>,
+    ) -> anyhow::Result<()> {
+        let wall_prefab = self.get_handle()?;
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(position.x, position.y, 0.15);
+        entities
+            .build_entity()
+            .with(wall_prefab, wall_prefabs)
+            .with(Wall, walls)
+            .with(transform, transforms)
+            .build();
+
+        Ok(())
+    }
+}
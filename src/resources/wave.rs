@@ -0,0 +1,232 @@
+use crate::resources::EnemyType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Wave {
+    // Seconds to wait before the wave starts, counted from the end of the previous wave (or the
+    // start of the game for the first one)
+    pub delay: f32,
+    // All groups in a wave start spawning at the same time
+    pub groups: Vec<WaveGroup>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaveGroup {
+    pub enemy: EnemyType,
+    pub count: usize,
+    // Seconds between each enemy in the group. Without it, the gameplay config's spawn interval
+    // is used
+    #[serde(default)]
+    pub interval: Option<f32>,
+    // How much tougher than usual the group's enemies are, e.g. 1.5 for half again their health
+    #[serde(default)]
+    pub health: Option<f32>,
+    // Names of the spawn and exit in the level this group goes through
+    pub spawn: String,
+    pub exit: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct SpawnOrder {
+    pub enemy: EnemyType,
+    pub health: f32,
+    pub spawn: String,
+    pub exit: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+struct GroupProgress {
+    spawned: usize,
+    // Seconds until the next enemy of the group spawns
+    cooldown: f32,
+}
+
+// Where a schedule is at, so that it can be saved and picked back up
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WaveProgress {
+    current: usize,
+    countdown: f32,
+    progress: Option<Vec<GroupProgress>>,
+}
+
+impl WaveProgress {
+    // How many waves a schedule needs for this to be restored into it
+    pub fn waves_needed(&self) -> usize {
+        self.current + 1
+    }
+}
+
+pub struct WaveSchedule {
+    waves: Vec<Wave>,
+    // Index of the wave currently spawning, or waiting to spawn
+    current: usize,
+    // Seconds left until the current wave starts. Only relevant while it hasn't started
+    countdown: f32,
+    // Only populated once the current wave has started
+    progress: Option<Vec<GroupProgress>>,
+}
+
+impl WaveSchedule {
+    pub fn new(waves: Vec<Wave>) -> Self {
+        let countdown = waves.first().map_or(0., |w| w.delay);
+        WaveSchedule {
+            waves,
+            current: 0,
+            countdown,
+            progress: None,
+        }
+    }
+
+    pub fn waves(&self) -> &[Wave] {
+        &self.waves
+    }
+
+    pub fn progress(&self) -> WaveProgress {
+        WaveProgress {
+            current: self.current,
+            countdown: self.countdown,
+            progress: self.progress.clone(),
+        }
+    }
+
+    // Fails if the progress doesn't match the waves, e.g. because the level changed since it was
+    // saved
+    pub fn restore(&mut self, progress: WaveProgress) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            progress.current <= self.waves.len(),
+            "Wave {} is past the last wave",
+            progress.current + 1
+        );
+        if let Some(ref groups) = progress.progress {
+            let wave = self.waves.get(progress.current);
+            anyhow::ensure!(
+                wave.is_some_and(|w| w.groups.len() == groups.len()),
+                "Wave {} does not have {} groups",
+                progress.current + 1,
+                groups.len()
+            );
+        }
+
+        self.current = progress.current;
+        self.countdown = progress.countdown;
+        self.progress = progress.progress;
+        Ok(())
+    }
+
+    // Jumps ahead so that the given wave, counting from 1, is the next one to spawn. Whatever
+    // came before it is skipped entirely
+    pub fn skip_to(&mut self, wave_number: usize) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            wave_number >= 1 && wave_number <= self.waves.len(),
+            "There's no wave {}, the level only has {}",
+            wave_number,
+            self.waves.len()
+        );
+        self.current = wave_number - 1;
+        self.countdown = self.waves[self.current].delay;
+        self.progress = None;
+        Ok(())
+    }
+
+    // Starts the next wave right away, as long as it's still being counted down to. Returns how
+    // many seconds early it was called
+    pub fn call_next(&mut self) -> Option<f32> {
+        if self.is_finished() || self.progress.is_some() {
+            return None;
+        }
+        let skipped = self.countdown.max(0.);
+        self.countdown = 0.;
+        Some(skipped)
+    }
+
+    // Adds a wave after the last one. If they'd all been through, it's counted down to right away
+    pub fn push(&mut self, wave: Wave) {
+        if self.is_finished() {
+            self.countdown = wave.delay;
+        }
+        self.waves.push(wave);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.waves.len()
+    }
+
+    // Number of the wave currently spawning (or about to), starting at 1
+    pub fn wave_number(&self) -> usize {
+        (self.current + 1).min(self.waves.len())
+    }
+
+    pub fn total_waves(&self) -> usize {
+        self.waves.len()
+    }
+
+    // Enemies from this wave and the ones after it that haven't spawned yet
+    pub fn remaining_to_spawn(&self) -> usize {
+        let upcoming: usize = self
+            .waves
+            .iter()
+            .skip(self.current)
+            .flat_map(|wave| wave.groups.iter())
+            .map(|group| group.count)
+            .sum();
+        let spawned: usize = self
+            .progress
+            .iter()
+            .flatten()
+            .map(|group_progress| group_progress.spawned)
+            .sum();
+        upcoming - spawned
+    }
+
+    // Moves the schedule forward by `delta` seconds, returning all the enemies that should be
+    // spawned in that time. Groups with no interval of their own use `default_interval`
+    pub fn advance(&mut self, delta: f32, default_interval: f32) -> Vec<SpawnOrder> {
+        let mut orders = vec![];
+        if self.is_finished() {
+            return orders;
+        }
+
+        let wave = &self.waves[self.current];
+        let progress = match self.progress {
+            Some(ref mut p) => p,
+            None => {
+                self.countdown -= delta;
+                if self.countdown > 0. {
+                    return orders;
+                }
+                self.progress
+                    .get_or_insert(vec![GroupProgress::default(); wave.groups.len()])
+            }
+        };
+
+        for (group, group_progress) in wave.groups.iter().zip(progress.iter_mut()) {
+            group_progress.cooldown -= delta;
+            while group_progress.spawned < group.count && group_progress.cooldown <= 0. {
+                orders.push(SpawnOrder {
+                    enemy: group.enemy,
+                    health: group.health.unwrap_or(1.),
+                    spawn: group.spawn.clone(),
+                    exit: group.exit.clone(),
+                });
+                group_progress.spawned += 1;
+                group_progress.cooldown += group.interval.unwrap_or(default_interval);
+            }
+        }
+
+        // Once every group is out, start counting down to the next wave
+        if wave
+            .groups
+            .iter()
+            .zip(progress.iter())
+            .all(|(group, group_progress)| group_progress.spawned >= group.count)
+        {
+            self.current += 1;
+            self.progress = None;
+            self.countdown = self.waves.get(self.current).map_or(0., |w| w.delay);
+        }
+
+        orders
+    }
+}
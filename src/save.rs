@@ -0,0 +1,450 @@
+use crate::{
+    components::{
+        ControlledCharacter, Destination, EnemyKind, Hitable, Restore, Tower, TowerKind, Wall,
+    },
+    pathfinding::Cell,
+    prefabs::{EnemyPrefab, TowerPrefab, WallPrefab},
+    resources::{
+        Economy, EndlessWaves, EnemyPrefabSet, EnemyType, GameRng, GameplayConfig, LevelMap, Lives,
+        RunStats, Score, SimulationClock, TowerPrefabSet, TowerType, WallPrefabSet, WaveProgress,
+        WaveSchedule,
+    },
+};
+use amethyst::{
+    assets::{AssetStorage, Handle, Prefab},
+    core::Transform,
+    ecs::{Entities, Entity, Join, Read, World, WorldExt, WriteExpect, WriteStorage},
+    utils::application_root_dir,
+};
+use log::{error, info};
+use nalgebra::{Point2, Vector3};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// Bump this whenever the format changes in a way older saves can't be read with
+const SAVE_VERSION: u32 = 1;
+
+/// Everything needed to pick a run back up where it was left.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SaveGame {
+    version: u32,
+    pub level: String,
+    pub endless: bool,
+    // Seed the run was played with, so it keeps getting the numbers it would have
+    pub seed: u64,
+    money: u32,
+    lives: u32,
+    score: u32,
+    stats: RunStats,
+    waves: WaveProgress,
+    // Missing if the game was saved before the player got to spawn
+    player: Option<SavedPlayer>,
+    towers: Vec<SavedTower>,
+    walls: Vec<Cell>,
+    enemies: Vec<SavedEnemy>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct SavedPlayer {
+    translation: Vector3<f32>,
+    locked: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct SavedTower {
+    tower_type: TowerType,
+    position: Point2<f32>,
+    active: bool,
+}
+
+// Paths aren't saved, they get recomputed from the position
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct SavedEnemy {
+    enemy_type: EnemyType,
+    position: Point2<f32>,
+    exit: Cell,
+    health: f32,
+    #[serde(default)]
+    max_health: Option<f32>,
+}
+
+/// Tick the game currently running was last saved or restored at, if it was. Runs left right
+/// after that can be picked back up, so they aren't over.
+#[derive(Default)]
+pub struct LastSave(pub Option<u64>);
+
+fn save_path() -> anyhow::Result<PathBuf> {
+    Ok(application_root_dir()?.join("saves").join("save.json"))
+}
+
+/// Saves the game currently running in the world, logging rather than failing as there's
+/// nothing else to be done about it.
+pub fn save_run(world: &World) {
+    match write(&capture(world)) {
+        Ok(()) => {
+            info!("Game saved");
+            if let Some(mut last_save) = world.try_fetch_mut::<LastSave>() {
+                last_save.0 = Some(world.read_resource::<SimulationClock>().tick);
+            }
+        }
+        Err(e) => error!("Failed to save the game: {}", e),
+    }
+}
+
+// Snapshots the game currently running in the world
+fn capture(world: &World) -> SaveGame {
+    let level_map = world.read_resource::<LevelMap>();
+    let transforms = world.read_storage::<Transform>();
+    let position =
+        |transform: &Transform| Point2::new(transform.translation().x, transform.translation().y);
+
+    // Entities added during the last tick, like the ones of a run that was just restored, only
+    // get their prefab's components on the next one. Until then what they'll get is waiting to be
+    // restored, and has to be saved in their place
+    let restores = world.read_storage::<Restore>();
+
+    let player = (&world.read_storage::<ControlledCharacter>(), &transforms)
+        .join()
+        .next()
+        .map(|(controlled_character, transform)| SavedPlayer {
+            translation: *transform.translation(),
+            locked: controlled_character.locked,
+        })
+        .or_else(|| {
+            restores.join().find_map(|restore| match *restore {
+                Restore::Player {
+                    translation,
+                    locked,
+                } => Some(SavedPlayer {
+                    translation,
+                    locked,
+                }),
+                _ => None,
+            })
+        });
+    let towers = (
+        &world.read_storage::<TowerKind>(),
+        world.read_storage::<Tower>().maybe(),
+        restores.maybe(),
+        &transforms,
+    )
+        .join()
+        .map(|(tower_kind, tower, restore, transform)| SavedTower {
+            tower_type: tower_kind.0,
+            position: position(transform),
+            // Towers start out unmanned, as their prefab has it
+            active: match restore {
+                Some(Restore::Tower { active }) => *active,
+                _ => tower.is_some_and(|tower| tower.active),
+            },
+        })
+        .collect();
+    let walls = (&world.read_storage::<Wall>(), &transforms)
+        .join()
+        .map(|(_, transform)| level_map.world_to_cell(position(transform)))
+        .collect();
+    let enemy_prefab_set = world.read_resource::<EnemyPrefabSet>();
+    let mut enemy_prefabs = world.write_resource::<AssetStorage<Prefab<EnemyPrefab>>>();
+    // Health enemies of the type get from their prefab, for the ones that were just spawned
+    let mut prefab_hitable = |enemy_type| {
+        let prefab = enemy_prefabs.get_mut(&enemy_prefab_set.get_handle(enemy_type).ok()?)?;
+        (0..prefab.len()).find_map(|index| {
+            prefab
+                .entity(index)?
+                .data()
+                .map(|data| data.hitable().clone())
+        })
+    };
+    let enemies = (
+        &world.read_storage::<EnemyKind>(),
+        &world.read_storage::<Destination>(),
+        world.read_storage::<Hitable>().maybe(),
+        restores.maybe(),
+        &transforms,
+    )
+        .join()
+        .filter_map(|(enemy_kind, destination, hitable, restore, transform)| {
+            let (health, max_health) = match restore {
+                Some(Restore::Enemy { health, max_health }) => (*health, *max_health),
+                Some(Restore::Toughened { factor }) => {
+                    let hitable = hitable.cloned().or_else(|| prefab_hitable(enemy_kind.0))?;
+                    (hitable.health * factor, Some(hitable.max_health * factor))
+                }
+                _ => hitable.map(|hitable| (hitable.health, Some(hitable.max_health)))?,
+            };
+            Some(SavedEnemy {
+                enemy_type: enemy_kind.0,
+                position: position(transform),
+                exit: destination.exit,
+                health,
+                max_health,
+            })
+        })
+        // Enemies that were just killed are only deleted at the end of the frame
+        .filter(|enemy| enemy.health > 0.)
+        .collect();
+
+    SaveGame {
+        version: SAVE_VERSION,
+        level: level_map.name().to_string(),
+        endless: world.has_value::<EndlessWaves>(),
+        seed: world.read_resource::<GameRng>().seed(),
+        money: world.read_resource::<Economy>().money,
+        lives: world.read_resource::<Lives>().remaining,
+        score: world.read_resource::<Score>().points,
+        stats: (*world.read_resource::<RunStats>()).clone(),
+        waves: world.read_resource::<WaveSchedule>().progress(),
+        player,
+        towers,
+        walls,
+        enemies,
+    }
+}
+
+fn write(save: &SaveGame) -> anyhow::Result<()> {
+    let path = save_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(save)?)
+        .map_err(|e| anyhow::anyhow!("Failed to write save {:?}: {}", path, e))
+}
+
+/// Reads the last save, if there is one.
+pub fn read() -> anyhow::Result<Option<SaveGame>> {
+    let path = save_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    read_file(&path).map(Some)
+}
+
+/// Reads a save from anywhere, not just where the game keeps its own.
+pub fn read_file(path: &Path) -> anyhow::Result<SaveGame> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read save {:?}: {}", path, e))?;
+    // Check the version on its own first, so that older saves get a clear error rather than
+    // whatever field happens to be missing
+    #[derive(Deserialize)]
+    struct Versioned {
+        version: u32,
+    }
+    let Versioned { version } = serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("Failed to read save {:?}: {}", path, e))?;
+    anyhow::ensure!(
+        version == SAVE_VERSION,
+        "Save {:?} is version {}, but only version {} is supported",
+        path,
+        version,
+        SAVE_VERSION
+    );
+    serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("Failed to read save {:?}: {}", path, e))
+}
+
+/// Removes the save, so that finished runs can't be continued.
+pub fn delete() -> anyhow::Result<()> {
+    let path = save_path()?;
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Puts a saved run back into the world. This expects a fresh game to already be set up for the
+/// save's level and mode, with `player` being its player entity.
+pub fn restore(world: &mut World, save: &SaveGame, player: Entity) -> anyhow::Result<()> {
+    {
+        let mut wave_schedule = world.write_resource::<WaveSchedule>();
+        // Endless waves are generated as they're needed, so the ones the save got to have to be
+        // generated again first
+        if let Some(endless) = world.try_fetch::<EndlessWaves>() {
+            let gameplay = world.read_resource::<GameplayConfig>();
+            endless.fill(
+                &mut wave_schedule,
+                &gameplay.endless,
+                save.waves.waves_needed(),
+            );
+        }
+        wave_schedule.restore(save.waves.clone())?;
+    }
+    world.insert(Economy { money: save.money });
+    world.insert(Lives {
+        remaining: save.lives,
+    });
+    world.insert(Score { points: save.score });
+    world.insert(save.stats.clone());
+
+    restore_walls(world, &save.walls)?;
+    restore_towers(world, &save.towers)?;
+    restore_enemies(world, &save.enemies)?;
+
+    if let Some(ref saved_player) = save.player {
+        world
+            .write_storage::<Restore>()
+            .insert(
+                player,
+                Restore::Player {
+                    translation: saved_player.translation,
+                    locked: saved_player.locked,
+                },
+            )
+            .expect("Failed to restore the player");
+    }
+
+    Ok(())
+}
+
+fn restore_walls(world: &mut World, cells: &[Cell]) -> anyhow::Result<()> {
+    world.exec(
+        #[allow(clippy::type_complexity)]
+        |(
+            entities,
+            mut wall_prefabs,
+            mut walls,
+            mut transforms,
+            mut level_map,
+            wall_prefab_set,
+        ): (
+            Entities,
+            WriteStorage<Handle<Prefab<WallPrefab>>>,
+            WriteStorage<Wall>,
+            WriteStorage<Transform>,
+            WriteExpect<LevelMap>,
+            Read<WallPrefabSet>,
+        )| {
+            for cell in cells {
+                level_map.set_walkable(*cell, false);
+                wall_prefab_set.add_wall(
+                    level_map.cell_to_world(*cell),
+                    &entities,
+                    &mut wall_prefabs,
+                    &mut walls,
+                    &mut transforms,
+                )?;
+            }
+            Ok(())
+        },
+    )
+}
+
+fn restore_towers(world: &mut World, towers: &[SavedTower]) -> anyhow::Result<()> {
+    world.exec(
+        #[allow(clippy::type_complexity)]
+        |(
+            entities,
+            mut tower_prefabs,
+            mut transforms,
+            mut tower_kinds,
+            mut restores,
+            tower_prefab_set,
+        ): (
+            Entities,
+            WriteStorage<Handle<Prefab<TowerPrefab>>>,
+            WriteStorage<Transform>,
+            WriteStorage<TowerKind>,
+            WriteStorage<Restore>,
+            Read<TowerPrefabSet>,
+        )| {
+            for tower in towers {
+                let entity = tower_prefab_set.add_tower(
+                    tower.tower_type,
+                    tower.position,
+                    &entities,
+                    &mut tower_prefabs,
+                    &mut transforms,
+                    &mut tower_kinds,
+                )?;
+                restores.insert(
+                    entity,
+                    Restore::Tower {
+                        active: tower.active,
+                    },
+                )?;
+            }
+            Ok(())
+        },
+    )
+}
+
+fn restore_enemies(world: &mut World, enemies: &[SavedEnemy]) -> anyhow::Result<()> {
+    world.exec(
+        #[allow(clippy::type_complexity)]
+        |(
+            entities,
+            mut enemy_prefabs,
+            mut transforms,
+            mut destinations,
+            mut enemy_kinds,
+            mut restores,
+            enemy_prefab_set,
+        ): (
+            Entities,
+            WriteStorage<Handle<Prefab<EnemyPrefab>>>,
+            WriteStorage<Transform>,
+            WriteStorage<Destination>,
+            WriteStorage<EnemyKind>,
+            WriteStorage<Restore>,
+            Read<EnemyPrefabSet>,
+        )| {
+            for enemy in enemies {
+                let entity = enemy_prefab_set.add_enemy(
+                    enemy.enemy_type,
+                    enemy.position,
+                    enemy.exit,
+                    &entities,
+                    &mut enemy_prefabs,
+                    &mut transforms,
+                    &mut destinations,
+                    &mut enemy_kinds,
+                )?;
+                restores.insert(
+                    entity,
+                    Restore::Enemy {
+                        health: enemy.health,
+                        max_health: enemy.max_health,
+                    },
+                )?;
+            }
+            Ok(())
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{harness::Harness, input::TickInput};
+
+    #[test]
+    fn saves_right_after_resuming_keep_everything() {
+        let mut harness = Harness::new("level1");
+        harness.spawn_player(harness.cell((5, 5)));
+        harness.spawn_enemy(harness.cell((27, 3)), "south");
+        harness.add_tower(harness.cell((20, 5)), true);
+        harness.step(TickInput::default());
+        let save = capture(&harness.world);
+
+        // Saved again before the resumed entities get their prefabs
+        let mut resumed = Harness::new("level1");
+        let player = resumed.spawn_player(resumed.cell((0, 0)));
+        restore(&mut resumed.world, &save, player).unwrap();
+        let saved_again = capture(&resumed.world);
+
+        assert_eq!(save.enemies.len(), 1);
+        assert_eq!(
+            format!(
+                "{:?}",
+                (
+                    &saved_again.player,
+                    &saved_again.towers,
+                    &saved_again.enemies
+                )
+            ),
+            format!("{:?}", (&save.player, &save.towers, &save.enemies))
+        );
+    }
+}
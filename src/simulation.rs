@@ -0,0 +1,169 @@
+use crate::{
+    components::Destination,
+    prefabs,
+    resources::{
+        Economy, EndlessWaves, Level, Lives, RunStats, Score, SimulationClock, TowerPlacement,
+        WaveSchedule, SIMULATION_STEP,
+    },
+    systems,
+};
+use amethyst::{
+    assets::PrefabLoaderSystemDesc,
+    core::{ArcThreadPool, SystemDesc},
+    ecs::{Dispatcher, DispatcherBuilder, Join, World, WorldExt},
+};
+use serde::Serialize;
+
+/// Runs all the gameplay systems one fixed step at a time, so that the outcome of a run only
+/// depends on its inputs and never on the frame rate. Everything purely visual stays in the main
+/// dispatcher.
+pub struct Simulation {
+    dispatcher: Dispatcher<'static, 'static>,
+}
+
+impl Simulation {
+    // Has to be created before any of the run's prefabs are added to entities, as the loaders only
+    // pick up handles inserted after they were set up
+    pub fn new(world: &mut World) -> Self {
+        // Prefabs get applied at the start of a tick rather than whenever a frame happens to run,
+        // and restored values go on top of them before anything else gets to see the entities
+        let mut builder = with_prefab_loaders(DispatcherBuilder::new(), world)
+            .with(
+                systems::RestoreSystem,
+                "restore_system",
+                &["tower_loader", "player_loader", "enemy_loader"],
+            )
+            .with_barrier()
+            .with(
+                systems::PlayerControlSystem::default(),
+                "player_control_system",
+                &[],
+            )
+            .with(
+                systems::ShooterControlSystem::default(),
+                "shooter_control_system",
+                &[],
+            )
+            .with(
+                systems::BulletSystem,
+                "bullet_system",
+                &["shooter_control_system"],
+            )
+            .with(systems::PathfindingSystem, "pathfinding_system", &[])
+            .with(
+                systems::EnemyMovementSystem,
+                "enemy_movement_system",
+                &["pathfinding_system"],
+            )
+            .with(
+                systems::PhysicsSystem,
+                "physics_system",
+                &[
+                    "bullet_system",
+                    "shooter_control_system",
+                    "player_control_system",
+                    "enemy_movement_system",
+                ],
+            )
+            .with(systems::PlacementSystem::default(), "placement_system", &[])
+            .with(
+                systems::EnemySpawnSystem::default(),
+                "enemy_spawn_system",
+                &[],
+            );
+        // Share the application's threads rather than spinning up new ones
+        if let Some(pool) = world.try_fetch::<ArcThreadPool>() {
+            builder = builder.with_pool((*pool).clone());
+        }
+
+        let mut dispatcher = builder.build();
+        dispatcher.setup(world);
+        world.insert(SimulationClock::default());
+
+        Simulation { dispatcher }
+    }
+
+    pub fn step(&mut self, world: &mut World) {
+        // Anything hot reloaded goes in before the tick rather than in the middle of one
+        systems::apply_reloads(world);
+        self.dispatcher.dispatch(world);
+        world.write_resource::<SimulationClock>().tick += 1;
+        if let Some(mut run_stats) = world.try_fetch_mut::<RunStats>() {
+            run_stats.time_played += SIMULATION_STEP;
+        }
+        world.maintain();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum RunOutcome {
+    Victory,
+    Defeat,
+}
+
+/// Puts everything a run on the level needs into the world. Enemies start spawning as soon as the
+/// simulation steps.
+pub fn insert_level(world: &mut World, level: Level) {
+    world.insert(TowerPlacement { placing: None });
+    world.insert(Economy { money: level.money });
+    world.insert(Lives {
+        remaining: level.lives,
+    });
+    world.insert(Score::default());
+    world.insert(RunStats::default());
+    world.insert(level.map);
+    world.insert(level.waves);
+}
+
+/// How the run in the world ended, if it's over.
+pub fn outcome(world: &World) -> Option<RunOutcome> {
+    if world.read_resource::<Lives>().remaining == 0 {
+        return Some(RunOutcome::Defeat);
+    }
+    let enemies_left = world.read_storage::<Destination>().join().next().is_some();
+    // Endless runs only ever end one way
+    let endless = world.has_value::<EndlessWaves>();
+    if world.read_resource::<WaveSchedule>().is_finished() && !enemies_left && !endless {
+        return Some(RunOutcome::Victory);
+    }
+    None
+}
+
+/// Adds loaders for all the prefabs used by gameplay. Anything that loads these prefabs without
+/// a simulation running (e.g. the loading screen) needs to run them itself.
+pub fn with_prefab_loaders(
+    builder: DispatcherBuilder<'static, 'static>,
+    world: &mut World,
+) -> DispatcherBuilder<'static, 'static> {
+    builder
+        .with(
+            PrefabLoaderSystemDesc::<prefabs::TowerPrefab>::default().build(world),
+            "tower_loader",
+            &[],
+        )
+        .with(
+            PrefabLoaderSystemDesc::<prefabs::BulletPrefab>::default().build(world),
+            "bullet_loader",
+            &[],
+        )
+        .with(
+            PrefabLoaderSystemDesc::<prefabs::PlayerPrefab>::default().build(world),
+            "player_loader",
+            &[],
+        )
+        .with(
+            PrefabLoaderSystemDesc::<prefabs::EnemyPrefab>::default().build(world),
+            "enemy_loader",
+            &[],
+        )
+        .with(
+            PrefabLoaderSystemDesc::<prefabs::WallPrefab>::default().build(world),
+            "wall_loader",
+            &[],
+        )
+        .with(
+            PrefabLoaderSystemDesc::<prefabs::HealthBarPrefab>::default().build(world),
+            "health_bar_loader",
+            &[],
+        )
+}
@@ -0,0 +1,53 @@
+use crate::state::ui;
+use amethyst::{
+    ecs::Entity,
+    input::{is_close_requested, is_key_down, VirtualKeyCode},
+    ui::UiCreator,
+    GameData, SimpleState, SimpleTrans, StateData, StateEvent, Trans,
+};
+
+const BUTTON_BACK: &str = "back_button";
+
+#[derive(Default)]
+pub struct Credits {
+    ui_root: Option<Entity>,
+}
+
+impl SimpleState for Credits {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        self.ui_root =
+            Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/credits.ron", ())));
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if let Some(ui_root) = self.ui_root.take() {
+            ui::delete_ui(data.world, ui_root);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let StateData { world, .. } = data;
+
+        match &event {
+            StateEvent::Window(event) => {
+                if is_close_requested(event) {
+                    return Trans::Quit;
+                }
+                if is_key_down(event, VirtualKeyCode::Escape) {
+                    return Trans::Pop;
+                }
+            }
+            StateEvent::Ui(event) if ui::is_clicked(world, event, BUTTON_BACK) => {
+                return Trans::Pop;
+            }
+            _ => {}
+        }
+        Trans::None
+    }
+}
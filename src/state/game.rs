@@ -1,27 +1,202 @@
 use crate::{
-    audio, prefabs,
-    resources::{EnemySpawning, FollowedObject, TowerPlacement},
+    audio,
+    input::{self, ActionBinding, GameBindingTypes, PendingSelection, PendingWaveCall, TickInput},
+    leaderboard, prefabs,
+    replay::{self, Replay},
+    resources::{
+        self, Economy, EndlessWaves, FollowedObject, GameRng, GameSpeed, GameplayConfig, Level,
+        LevelMap, Lives, RngStream, RunStats, Score, SimulationClock, TickPacer, TowerPlacement,
+        WaveSchedule,
+    },
+    save::{self, SaveGame},
+    simulation::{self, RunOutcome, Simulation},
+    state::{ui, GameOver, Paused, RunSummary, Victory},
 };
 use amethyst::{
-    assets::{AssetStorage, Handle, Prefab},
-    audio::{output::Output, Source},
-    ecs::{Entity, Read, ReadExpect},
-    input::{is_close_requested, is_key_down, VirtualKeyCode},
-    prelude::{Builder, WorldExt},
+    assets::{Handle, Prefab},
+    ecs::{Entities, Entity, Join, ReadStorage},
+    input::{is_close_requested, is_key_down, InputHandler, VirtualKeyCode},
+    prelude::{Builder, World, WorldExt},
+    renderer::Camera,
     ui::UiCreator,
+    utils::application_root_dir,
     GameData, SimpleState, SimpleTrans, StateData, StateEvent, Trans,
 };
+use log::{error, info};
+
+const BUTTON_SPEED: &str = "speed_button";
+// Runs on the leaderboard shown once an endless run is over
+const LEADERBOARD_SHOWN: usize = 5;
+
+/// Where a run starts instead of the beginning of the level, mostly for testing later waves.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RunStart {
+    // Counting from 1
+    pub wave: Option<usize>,
+    pub money: Option<u32>,
+}
+
+impl RunStart {
+    fn is_beginning(&self) -> bool {
+        self.wave.is_none() && self.money.is_none()
+    }
+}
 
 pub struct Game {
-    pub background_prefab: Handle<Prefab<prefabs::BackgroundPrefab>>,
-    pub player_prefab: Handle<Prefab<prefabs::PlayerPrefab>>,
-    pub ui_root: Option<Entity>,
+    background_prefab: Handle<Prefab<prefabs::BackgroundPrefab>>,
+    player_prefab: Handle<Prefab<prefabs::PlayerPrefab>>,
+    // Name of the file in assets/levels to play, without the extension
+    level: String,
+    // Run to pick back up instead of starting a new one
+    save: Option<SaveGame>,
+    start: RunStart,
+    // Seed of everything random in the run
+    seed: u64,
+    // Whether waves keep coming once the level's own are over
+    endless: bool,
+    // Whether the run can make it onto the leaderboard, which replays and runs that skip ahead
+    // can't
+    ranked: bool,
+    // Whether the run already went on the leaderboard
+    recorded: bool,
+    // Input of every tick of a new run, written out once it's over
+    recording: Option<Replay>,
+    // Run being played back, in which case the player's input is ignored until it's over
+    playback: Option<Replay>,
+    // Only around while the game is running
+    simulation: Option<Simulation>,
+    pacer: TickPacer,
+    // The speed changes once the key is let go of
+    speed_was_pressed: bool,
+    ui_root: Option<Entity>,
+}
+
+impl Game {
+    pub fn new(
+        background_prefab: Handle<Prefab<prefabs::BackgroundPrefab>>,
+        player_prefab: Handle<Prefab<prefabs::PlayerPrefab>>,
+        level: String,
+    ) -> Self {
+        let seed = rand::random();
+        Game {
+            background_prefab,
+            player_prefab,
+            recording: Some(Replay::new(level.clone(), seed)),
+            level,
+            save: None,
+            start: RunStart::default(),
+            seed,
+            endless: false,
+            ranked: true,
+            recorded: false,
+            playback: None,
+            simulation: None,
+            pacer: TickPacer::default(),
+            speed_was_pressed: false,
+            ui_root: None,
+        }
+    }
+
+    pub fn resume(
+        background_prefab: Handle<Prefab<prefabs::BackgroundPrefab>>,
+        player_prefab: Handle<Prefab<prefabs::PlayerPrefab>>,
+        save: SaveGame,
+    ) -> Self {
+        // Replays always start from the beginning of a run, so resumed ones aren't recorded
+        let game = Game {
+            level: save.level.clone(),
+            endless: save.endless,
+            recording: None,
+            ..Game::new(background_prefab, player_prefab, String::new())
+        }
+        .seeded(save.seed);
+        Game {
+            save: Some(save),
+            ..game
+        }
+    }
+
+    pub fn replay(
+        background_prefab: Handle<Prefab<prefabs::BackgroundPrefab>>,
+        player_prefab: Handle<Prefab<prefabs::PlayerPrefab>>,
+        replay: Replay,
+    ) -> Self {
+        Game {
+            level: replay.level.clone(),
+            seed: replay.seed,
+            endless: replay.endless,
+            playback: Some(replay),
+            recording: None,
+            ranked: false,
+            ..Game::new(background_prefab, player_prefab, String::new())
+        }
+    }
+
+    // Replays always start from the beginning of a run, so runs that skip ahead aren't recorded
+    pub fn starting_at(self, start: RunStart) -> Self {
+        Game {
+            recording: self.recording.filter(|_| start.is_beginning()),
+            ranked: self.ranked && start.is_beginning(),
+            start,
+            ..self
+        }
+    }
+
+    // Plays the run with the given seed instead of a random one, to get the same run again
+    pub fn seeded(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        if let Some(ref mut recording) = self.recording {
+            recording.seed = seed;
+        }
+        self
+    }
+
+    pub fn endless(mut self) -> Self {
+        self.endless = true;
+        if let Some(ref mut recording) = self.recording {
+            recording.endless = true;
+        }
+        self
+    }
+
+    // A brand new run on the same level, starting from the same point. It keeps the seed, so
+    // endless runs get the same waves again
+    pub fn restart(&self) -> Self {
+        let game = Game::new(
+            self.background_prefab.clone(),
+            self.player_prefab.clone(),
+            self.level.clone(),
+        )
+        .starting_at(self.start)
+        .seeded(self.seed);
+        if self.endless {
+            game.endless()
+        } else {
+            game
+        }
+    }
 }
 
 impl SimpleState for Game {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let StateData { world, .. } = data;
 
+        // Without a level there's no run, update takes us back to the menu
+        let level = match load_level(&self.level) {
+            Ok(level) => level,
+            Err(e) => {
+                error!("Failed to load level {:?}: {}", self.level, e);
+                return;
+            }
+        };
+
+        self.simulation = Some(Simulation::new(world));
+        world.insert(TickInput::default());
+        world.insert(PendingSelection::default());
+        world.insert(PendingWaveCall::default());
+        world.insert(GameSpeed::default());
+        world.insert(save::LastSave::default());
+
         // Setup UI
         self.ui_root =
             Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/hud.ron", ())));
@@ -40,29 +215,278 @@ impl SimpleState for Game {
             e: player_entity,
             hard_lock: false,
         });
-        world.insert(TowerPlacement { placing: false });
-        world.insert(EnemySpawning);
+
+        simulation::insert_level(world, level);
+        // Logged so that runs can be played again with --seed, e.g. to reproduce a bug
+        info!("Run seed: {}", self.seed);
+        let rng = GameRng::new(self.seed);
+        if self.endless {
+            let endless = EndlessWaves::new(
+                rng.stream_seed(RngStream::EndlessWaves),
+                &world.read_resource::<WaveSchedule>(),
+            );
+            world.insert(endless);
+        }
+        world.insert(rng);
+
+        if let Some(save) = self.save.take() {
+            match save::restore(world, &save, player_entity) {
+                Ok(()) => world.insert(save::LastSave(Some(0))),
+                Err(e) => error!("Failed to restore the saved game: {}", e),
+            }
+        }
+        if let Some(wave) = self.start.wave {
+            let mut wave_schedule = world.write_resource::<WaveSchedule>();
+            if let Some(endless) = world.try_fetch::<EndlessWaves>() {
+                let gameplay = world.read_resource::<GameplayConfig>();
+                endless.fill(&mut wave_schedule, &gameplay.endless, wave);
+            }
+            if let Err(e) = wave_schedule.skip_to(wave) {
+                error!("Failed to skip ahead: {}", e);
+            }
+        }
+        if let Some(money) = self.start.money {
+            world.write_resource::<Economy>().money = money;
+        }
 
         // Start the music
-        world.exec(
-            |(sounds, storage, audio_output): (
-                ReadExpect<'_, audio::Sounds>,
-                Read<'_, AssetStorage<Source>>,
-                Option<Read<'_, Output>>,
-            )| { audio::play_music(&*sounds, &storage, audio_output.as_deref()) },
+        audio::start_music(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        // However an endless run ends, quitting to the menu included, it goes on the leaderboard.
+        // Unless it was just saved, as then it isn't over
+        if self.endless && self.ranked && !self.recorded && self.simulation.is_some() {
+            let tick = world.read_resource::<SimulationClock>().tick;
+            if world.read_resource::<save::LastSave>().0 != Some(tick) {
+                self.leaderboard(&RunSummary::new(world));
+            }
+        }
+
+        // Everything but the camera belongs to this run, UI included
+        let entities: Vec<Entity> = world.exec(
+            |(entities, cameras): (Entities<'_>, ReadStorage<'_, Camera>)| {
+                (&entities, !&cameras)
+                    .join()
+                    .map(|(entity, _)| entity)
+                    .collect()
+            },
         );
+        world
+            .delete_entities(&entities)
+            .expect("Failed to tear down the game");
+        self.ui_root = None;
+        self.simulation = None;
+
+        if let Some(recording) = self.recording.take().filter(|r| !r.is_empty()) {
+            if let Err(e) = replay::write(&recording) {
+                error!("Failed to write the replay: {}", e);
+            }
+        }
+
+        // Without these, gameplay systems sit idle until the next run starts
+        world.remove::<FollowedObject>();
+        world.remove::<TowerPlacement>();
+        world.remove::<Economy>();
+        world.remove::<Lives>();
+        world.remove::<Score>();
+        world.remove::<RunStats>();
+        world.remove::<LevelMap>();
+        world.remove::<WaveSchedule>();
+        world.remove::<EndlessWaves>();
+        world.remove::<GameRng>();
+        world.remove::<PendingSelection>();
+        world.remove::<PendingWaveCall>();
+        world.remove::<GameSpeed>();
+        world.remove::<save::LastSave>();
+
+        audio::stop_music(world);
     }
 
     fn handle_event(
         &mut self,
-        mut _data: StateData<'_, GameData<'_, '_>>,
+        data: StateData<'_, GameData<'_, '_>>,
         event: StateEvent,
     ) -> SimpleTrans {
-        if let StateEvent::Window(event) = &event {
-            if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
-                return Trans::Quit;
+        let StateData { world, .. } = data;
+
+        match &event {
+            StateEvent::Window(event) => {
+                // Don't lose the run just because the window got closed. Replays aren't the
+                // player's run to save though
+                if is_close_requested(event) {
+                    if self.playback.is_none() && self.simulation.is_some() {
+                        save::save_run(world);
+                    }
+                    return Trans::Quit;
+                }
+                if is_key_down(event, VirtualKeyCode::Escape) {
+                    return Trans::Push(Box::new(Paused::default()));
+                }
+            }
+            StateEvent::Ui(event) if ui::is_clicked(world, event, BUTTON_SPEED) => {
+                change_speed(world);
             }
+            _ => {}
         }
         Trans::None
     }
+
+    // Only runs while the game isn't paused, as it's not on top of the stack otherwise
+    fn fixed_update(&mut self, data: StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let StateData { world, .. } = data;
+
+        // The level failed to load, update takes us back to the menu
+        if self.simulation.is_none() {
+            return Trans::None;
+        }
+
+        // Faster speeds run more ticks rather than longer ones, so nothing moves far enough in
+        // one tick to go through what it should have hit
+        let speed = *world.read_resource::<GameSpeed>();
+        for _ in 0..self.pacer.ticks(speed) {
+            // No ticks past the end of the run
+            if simulation::outcome(world).is_some() {
+                break;
+            }
+            self.tick(world);
+        }
+
+        Trans::None
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let StateData { world, .. } = data;
+
+        if self.simulation.is_none() {
+            return Trans::Pop;
+        }
+
+        let speed_pressed = world
+            .read_resource::<InputHandler<GameBindingTypes>>()
+            .action_is_down(&ActionBinding::ChangeSpeed)
+            .unwrap_or(false);
+        if !speed_pressed && self.speed_was_pressed {
+            change_speed(world);
+        }
+        self.speed_was_pressed = speed_pressed;
+
+        // Switching away from the game tears it down, the end screens start a new one for retries.
+        // Finished runs can't be continued, so their save goes away too
+        match simulation::outcome(world) {
+            Some(outcome) => {
+                delete_save();
+                let summary = RunSummary::new(world);
+                match outcome {
+                    RunOutcome::Defeat => {
+                        let game_over = GameOver::new(summary.clone(), self.restart());
+                        Trans::Switch(Box::new(if self.endless {
+                            game_over.with_leaderboard(self.leaderboard(&summary))
+                        } else {
+                            game_over
+                        }))
+                    }
+                    RunOutcome::Victory => {
+                        Trans::Switch(Box::new(Victory::new(summary, self.restart())))
+                    }
+                }
+            }
+            None => Trans::None,
+        }
+    }
+}
+
+impl Game {
+    fn tick(&mut self, world: &mut World) {
+        if let Some(ref mut simulation) = self.simulation {
+            let played = self.playback.as_mut().and_then(|p| p.next_input());
+            // Once the replay runs out, the player takes over from wherever it left off
+            if played.is_none() && self.playback.take().is_some() {
+                info!("The replay is over");
+            }
+            let input = match played {
+                // Show the speed the replay is going at, and keep going at it once it's over
+                Some(input) => {
+                    world.insert(input.speed);
+                    input
+                }
+                None => input::capture_tick_input(world),
+            };
+            if let Some(ref mut recording) = self.recording {
+                recording.record(&input);
+            }
+            world.insert(input);
+
+            simulation.step(world);
+        }
+    }
+
+    // Puts the endless run that just ended on the leaderboard if it can go there, and describes
+    // where it stands
+    fn leaderboard(&mut self, summary: &RunSummary) -> String {
+        let result = if self.ranked && !self.recorded {
+            self.recorded = true;
+            leaderboard::record(leaderboard::Entry {
+                level: self.level.clone(),
+                wave: summary.waves_survived + 1,
+                score: summary.score,
+                seed: self.seed,
+            })
+        } else {
+            leaderboard::read().map(|leaderboard| (leaderboard, None))
+        };
+        match result {
+            Ok((leaderboard, rank)) => leaderboard.describe(&self.level, rank, LEADERBOARD_SHOWN),
+            Err(e) => {
+                error!("{}", e);
+                String::new()
+            }
+        }
+    }
+}
+
+// Takes effect on the next frame's ticks, and goes into their input for replays
+fn change_speed(world: &mut World) {
+    let mut speed = world.write_resource::<GameSpeed>();
+    *speed = speed.next();
+}
+
+fn load_level(name: &str) -> anyhow::Result<Level> {
+    resources::load_level(
+        application_root_dir()?
+            .join("assets")
+            .join("levels")
+            .join(format!("{}.ron", name)),
+    )
+}
+
+fn delete_save() {
+    if let Err(e) = save::delete() {
+        error!("Failed to delete the saved game: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless;
+    use amethyst::{assets::ProgressCounter, ecs::DispatcherBuilder};
+
+    #[test]
+    fn runs_without_a_level_go_back_to_the_menu() {
+        let (mut world, player_prefab) = headless::headless_world().unwrap();
+        let background_prefab = prefabs::load_background(&mut world, &mut ProgressCounter::new());
+        let mut game = Game::new(background_prefab, player_prefab, "missing".to_string());
+        let mut game_data = GameData::new(DispatcherBuilder::new().build());
+
+        game.on_start(StateData::new(&mut world, &mut game_data));
+        // Fixed updates come first, before update gets to leave
+        game.fixed_update(StateData::new(&mut world, &mut game_data));
+        assert!(matches!(
+            game.update(&mut StateData::new(&mut world, &mut game_data)),
+            Trans::Pop
+        ));
+    }
 }
@@ -1,43 +1,85 @@
-use crate::{animation::AnimationId, audio, components::Splash, prefabs, resources, state::Game};
+use crate::{
+    animation::AnimationId,
+    audio,
+    components::Splash,
+    options::LaunchOptions,
+    prefabs, resources, simulation,
+    state::{ui, MainMenu},
+};
 use amethyst::{
     animation::{
         get_animation_set, AnimationCommand, AnimationControlSet, AnimationSet, EndControl,
     },
-    assets::{AssetStorage, Handle, Prefab, ProgressCounter},
+    assets::{AssetStorage, Completion, Handle, Prefab, ProgressCounter},
     audio::{output::Output, Source},
-    core::transform::Transform,
-    ecs::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, WriteStorage},
+    core::{transform::Transform, ArcThreadPool, Time},
+    ecs::{
+        Dispatcher, DispatcherBuilder, Entities, Entity, Join, Read, ReadExpect, ReadStorage,
+        WriteStorage,
+    },
     input::{is_close_requested, is_key_down, VirtualKeyCode},
     prelude::{Builder, World, WorldExt},
     renderer::{camera::Camera, sprite::SpriteRender},
+    ui::UiCreator,
     window::ScreenDimensions,
+    winit::{ElementState, Event, WindowEvent},
     GameData, SimpleState, SimpleTrans, StateData, StateEvent, Trans,
 };
-use log::info;
-use std::time::{Duration, Instant};
+use log::{error, info, warn};
+
+const LOADING_TEXT: &str = "loading_text";
+const PROGRESS_BAR_FILL: &str = "loading_bar_fill";
+// Width of the progress bar once it's full, same as in ui/loading.ron
+const PROGRESS_BAR_WIDTH: f32 = 400.;
 
 #[derive(Default)]
 pub struct Loading {
+    // Without the splash, the main menu comes up as soon as everything is loaded
+    show_splash: bool,
     loading_progress_counter: Option<ProgressCounter>,
     main_progress_counter: Option<ProgressCounter>,
+    // Assets the game can do without, like audio. Failing to load them is only worth a warning
+    optional_progress_counter: ProgressCounter,
+    // Assets that failed to load. Once there are any, loading is stuck and only shows them
+    failed: Vec<String>,
+    ui_root: Option<Entity>,
     items_done_last: Option<usize>,
     background_prefab: Option<Handle<Prefab<prefabs::BackgroundPrefab>>>,
     player_prefab: Option<Handle<Prefab<prefabs::PlayerPrefab>>>,
-    counter_end: Option<Instant>,
+    // Seconds left of the splash animation, once it's started
+    splash_remaining: Option<f32>,
     animation_entity: Option<Entity>,
+    // Gameplay prefabs are only processed by the simulation, which isn't around yet
+    prefab_loaders: Option<Dispatcher<'static, 'static>>,
 }
 
 impl SimpleState for Loading {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let StateData { world, .. } = data;
         self.loading_progress_counter = Some(ProgressCounter::new());
-        self.animation_entity = Some(prefabs::load_splash_screen(
-            world,
-            self.loading_progress_counter.as_mut().unwrap(),
-        ));
-        audio::initialise_audio(world, self.loading_progress_counter.as_mut().unwrap());
+        if self.show_splash {
+            self.animation_entity = Some(prefabs::load_splash_screen(
+                world,
+                self.loading_progress_counter.as_mut().unwrap(),
+            ));
+        }
+        audio::initialise_audio(world, &mut self.optional_progress_counter);
+        // Without an output everything goes on as if there was no sound device
+        if world.read_resource::<LaunchOptions>().no_audio {
+            world.remove::<Output>();
+        }
         // Creates a new camera (needed for splash screen)
         initialise_camera(world);
+        self.ui_root =
+            Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/loading.ron", ())));
+
+        let mut builder = simulation::with_prefab_loaders(DispatcherBuilder::new(), world);
+        if let Some(pool) = world.try_fetch::<ArcThreadPool>() {
+            builder = builder.with_pool((*pool).clone());
+        }
+        let mut prefab_loaders = builder.build();
+        prefab_loaders.setup(world);
+        self.prefab_loaders = Some(prefab_loaders);
     }
 
     fn handle_event(
@@ -49,23 +91,45 @@ impl SimpleState for Loading {
             if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
                 return Trans::Quit;
             }
+            // Cut the splash short, but only once there's nothing left to wait for
+            if is_key_or_click(event)
+                && self.loading_progress_counter.is_none()
+                && self.main_progress_counter.is_none()
+            {
+                if let Some(ref mut splash_remaining) = self.splash_remaining {
+                    *splash_remaining = 0.;
+                }
+            }
         }
         Trans::None
     }
 
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
         let StateData { world, .. } = data;
+        if !self.failed.is_empty() {
+            return Trans::None;
+        }
 
         // Checks if we are still loading data
         if let Some(ref loading_progress_counter) = self.loading_progress_counter {
-            // Checks progress
-            if loading_progress_counter.is_complete() {
+            if let Some(failed) = failed_assets(loading_progress_counter) {
+                self.fail(world, failed);
+                return Trans::None;
+            }
+            // Checks progress. The buzz goes with the splash, so wait for it too
+            if loading_progress_counter.is_complete()
+                && self.optional_progress_counter.num_loading() == 0
+            {
                 info!("Splash screen loaded");
 
-                // Load and start splash screen animations
-                self.start_animation(world);
-                // Start the buzzing audio
-                self.start_buzz_audio(world);
+                if self.show_splash {
+                    // Load and start splash screen animations
+                    self.start_animation(world);
+                    // Start the buzzing audio
+                    self.start_buzz_audio(world);
+                } else {
+                    self.splash_remaining = Some(0.);
+                }
 
                 self.loading_progress_counter = None;
                 self.main_progress_counter = Some(ProgressCounter::new());
@@ -89,6 +153,18 @@ impl SimpleState for Loading {
                 );
                 world.insert(enemy_prefab_set);
 
+                let wall_prefab_set = resources::WallPrefabSet::new(
+                    world,
+                    self.main_progress_counter.as_mut().unwrap(),
+                );
+                world.insert(wall_prefab_set);
+
+                let health_bar_prefab_set = resources::HealthBarPrefabSet::new(
+                    world,
+                    self.main_progress_counter.as_mut().unwrap(),
+                );
+                world.insert(health_bar_prefab_set);
+
                 self.background_prefab = Some(prefabs::load_background(
                     world,
                     self.main_progress_counter.as_mut().unwrap(),
@@ -99,19 +175,39 @@ impl SimpleState for Loading {
                 ));
             }
         } else if let Some(ref main_progress_counter) = self.main_progress_counter {
+            if let Some(ref mut prefab_loaders) = self.prefab_loaders {
+                prefab_loaders.dispatch(world);
+            }
+
             // Checks progress
-            if main_progress_counter.is_complete() {
+            if let Some(failed) = failed_assets(main_progress_counter) {
+                self.fail(world, failed);
+            } else if main_progress_counter.is_complete() {
                 info!("LOADED");
+                for e in self.optional_progress_counter.errors() {
+                    warn!(
+                        "Carrying on without {:?}, which failed to load: {}",
+                        e.asset_name, e.error
+                    );
+                }
 
                 // All data loaded
                 self.main_progress_counter = None;
-            } else {
-                let errors = main_progress_counter.errors();
-                if !errors.is_empty() {
-                    println!("ERRORS: {:?}", errors);
+                self.prefab_loaders = None;
+                if let Some(ui_root) = self.ui_root.take() {
+                    ui::delete_ui(world, ui_root);
                 }
-
+            } else {
                 let num_finished = main_progress_counter.num_finished();
+                let fraction =
+                    num_finished as f32 / main_progress_counter.num_assets().max(1) as f32;
+                ui::set_width(world, PROGRESS_BAR_FILL, fraction * PROGRESS_BAR_WIDTH);
+                ui::set_text(
+                    world,
+                    LOADING_TEXT,
+                    &format!("Loading {:.0}%", fraction * 100.),
+                );
+
                 let print = match self.items_done_last {
                     Some(l) => num_finished != l,
                     None => true,
@@ -125,18 +221,18 @@ impl SimpleState for Loading {
             }
         } else {
             // If animation ended after loading, exit
-            if let Some(counter_end) = self.counter_end {
-                if counter_end < Instant::now() {
+            if let Some(ref mut splash_remaining) = self.splash_remaining {
+                *splash_remaining -= world.read_resource::<Time>().delta_seconds();
+                if *splash_remaining <= 0. {
                     if let Some(animation_entity) = self.animation_entity {
                         world
                             .delete_entity(animation_entity)
                             .expect("Failed to delete splash screen");
                     }
-                    return Trans::Replace(Box::new(Game {
-                        background_prefab: self.background_prefab.as_ref().unwrap().clone(),
-                        player_prefab: self.player_prefab.as_ref().unwrap().clone(),
-                        ui_root: None,
-                    }));
+                    return Trans::Replace(Box::new(MainMenu::new(
+                        self.background_prefab.as_ref().unwrap().clone(),
+                        self.player_prefab.as_ref().unwrap().clone(),
+                    )));
                 }
             }
         }
@@ -146,6 +242,27 @@ impl SimpleState for Loading {
 }
 
 impl Loading {
+    pub fn new(show_splash: bool) -> Self {
+        Loading {
+            show_splash,
+            ..Default::default()
+        }
+    }
+
+    // Stops loading for good and lists what went wrong, as the game can't go on without it
+    fn fail(&mut self, world: &mut World, failed: Vec<String>) {
+        error!("Failed to load: {}", failed.join(", "));
+        ui::set_text(
+            world,
+            LOADING_TEXT,
+            &format!(
+                "Failed to load:\n{}\n\nPress Escape to quit",
+                failed.join("\n")
+            ),
+        );
+        self.failed = failed;
+    }
+
     fn start_animation(&mut self, world: &mut World) {
         // Execute a pass similar to a system
         world.exec(
@@ -173,7 +290,7 @@ impl Loading {
         );
 
         // Due to the audio, we want to run for 4s
-        self.counter_end = Some(Instant::now() + Duration::from_secs_f32(4.0));
+        self.splash_remaining = Some(4.0);
     }
 
     fn start_buzz_audio(&self, world: &mut World) {
@@ -188,6 +305,34 @@ impl Loading {
     }
 }
 
+fn is_key_or_click(event: &Event) -> bool {
+    match event {
+        Event::WindowEvent {
+            event: WindowEvent::KeyboardInput { input, .. },
+            ..
+        } => input.state == ElementState::Pressed,
+        Event::WindowEvent {
+            event: WindowEvent::MouseInput { state, .. },
+            ..
+        } => *state == ElementState::Pressed,
+        _ => false,
+    }
+}
+
+// Descriptions of the assets that failed to load, once loading has failed
+fn failed_assets(progress_counter: &ProgressCounter) -> Option<Vec<String>> {
+    if progress_counter.complete() != Completion::Failed {
+        return None;
+    }
+    Some(
+        progress_counter
+            .errors()
+            .into_iter()
+            .map(|e| format!("{} ({}): {}", e.asset_name, e.asset_type_name, e.error))
+            .collect(),
+    )
+}
+
 fn initialise_camera(world: &mut World) {
     info!("Initialising camera");
     let (width, height) = {
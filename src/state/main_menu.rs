@@ -0,0 +1,249 @@
+use crate::{
+    options::LaunchOptions,
+    prefabs, replay, save,
+    state::{ui, Credits, Game, RunStart, Settings},
+};
+use amethyst::{
+    assets::{Handle, Prefab},
+    ecs::Entity,
+    input::{is_close_requested, is_key_down, VirtualKeyCode},
+    prelude::{World, WorldExt},
+    ui::UiCreator,
+    utils::application_root_dir,
+    GameData, SimpleState, SimpleTrans, StateData, StateEvent, Trans,
+};
+use log::{error, warn};
+use std::fs;
+
+const BUTTON_NEW_GAME: &str = "new_game_button";
+const BUTTON_CONTINUE: &str = "continue_button";
+const BUTTON_LEVEL: &str = "level_button";
+const BUTTON_ENDLESS: &str = "endless_button";
+const ENDLESS_TEXT: &str = "endless_button_btn_txt";
+const BUTTON_SETTINGS: &str = "settings_button";
+const BUTTON_CREDITS: &str = "credits_button";
+const BUTTON_REPLAY: &str = "replay_button";
+const BUTTON_QUIT: &str = "quit_button";
+const LEVEL_TEXT: &str = "level_text";
+
+pub struct MainMenu {
+    background_prefab: Handle<Prefab<prefabs::BackgroundPrefab>>,
+    player_prefab: Handle<Prefab<prefabs::PlayerPrefab>>,
+    // Names of all the files in assets/levels, and which one will be played
+    levels: Vec<String>,
+    selected_level: usize,
+    // Whether new runs keep going once the level's waves are over
+    endless: bool,
+    // Given on the command line, applies to every new run started from here
+    start: RunStart,
+    seed: Option<u64>,
+    ui_root: Option<Entity>,
+}
+
+impl MainMenu {
+    pub fn new(
+        background_prefab: Handle<Prefab<prefabs::BackgroundPrefab>>,
+        player_prefab: Handle<Prefab<prefabs::PlayerPrefab>>,
+    ) -> Self {
+        MainMenu {
+            background_prefab,
+            player_prefab,
+            levels: find_levels(),
+            selected_level: 0,
+            endless: false,
+            start: RunStart::default(),
+            seed: None,
+            ui_root: None,
+        }
+    }
+
+    fn show(&mut self, world: &mut World) {
+        self.ui_root =
+            Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/main_menu.ron", ())));
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        if let Some(ui_root) = self.ui_root.take() {
+            ui::delete_ui(world, ui_root);
+        }
+    }
+
+    fn new_game(&self, level: String) -> Game {
+        let game = Game::new(
+            self.background_prefab.clone(),
+            self.player_prefab.clone(),
+            level,
+        )
+        .starting_at(self.start);
+        let game = match self.seed {
+            Some(seed) => game.seeded(seed),
+            None => game,
+        };
+        if self.endless {
+            game.endless()
+        } else {
+            game
+        }
+    }
+
+    // A run asked for on the command line, which skips the menu. The menu is still underneath
+    // for when the run is over. Only happens once
+    fn launch_game(&mut self, world: &mut World) -> Option<Game> {
+        let mut options = world.write_resource::<LaunchOptions>();
+        let game = if let Some(level) = options.level.take() {
+            match self.levels.iter().position(|l| *l == level) {
+                Some(index) => self.selected_level = index,
+                None => {
+                    error!("There's no level called {:?}", level);
+                    return None;
+                }
+            }
+            self.new_game(level)
+        } else if let Some(path) = options.save.take() {
+            match save::read_file(&path) {
+                Ok(save) => Game::resume(
+                    self.background_prefab.clone(),
+                    self.player_prefab.clone(),
+                    save,
+                ),
+                Err(e) => {
+                    error!("{}", e);
+                    return None;
+                }
+            }
+        } else {
+            return None;
+        };
+        Some(game)
+    }
+}
+
+impl SimpleState for MainMenu {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        {
+            let options = data.world.read_resource::<LaunchOptions>();
+            self.endless = options.endless;
+            self.start = RunStart {
+                wave: options.wave,
+                money: options.money,
+            };
+            self.seed = options.seed;
+        }
+        self.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    // The game gets pushed on top of the menu, and popped back off to return to it
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.show(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let StateData { world, .. } = data;
+
+        match &event {
+            StateEvent::Window(event)
+                if is_close_requested(event) || is_key_down(event, VirtualKeyCode::Escape) =>
+            {
+                return Trans::Quit;
+            }
+            StateEvent::Ui(event) => {
+                if ui::is_clicked(world, event, BUTTON_NEW_GAME) {
+                    if let Some(level) = self.levels.get(self.selected_level) {
+                        return Trans::Push(Box::new(self.new_game(level.clone())));
+                    }
+                }
+                if ui::is_clicked(world, event, BUTTON_CONTINUE) {
+                    match save::read() {
+                        Ok(Some(save)) => {
+                            return Trans::Push(Box::new(Game::resume(
+                                self.background_prefab.clone(),
+                                self.player_prefab.clone(),
+                                save,
+                            )));
+                        }
+                        Ok(None) => warn!("There is no saved game to continue"),
+                        Err(e) => error!("{}", e),
+                    }
+                }
+                if ui::is_clicked(world, event, BUTTON_LEVEL) && !self.levels.is_empty() {
+                    self.selected_level = (self.selected_level + 1) % self.levels.len();
+                }
+                if ui::is_clicked(world, event, BUTTON_ENDLESS) {
+                    self.endless = !self.endless;
+                }
+                if ui::is_clicked(world, event, BUTTON_SETTINGS) {
+                    return Trans::Push(Box::new(Settings::default()));
+                }
+                if ui::is_clicked(world, event, BUTTON_CREDITS) {
+                    return Trans::Push(Box::new(Credits::default()));
+                }
+                if ui::is_clicked(world, event, BUTTON_REPLAY) {
+                    match replay::read() {
+                        Ok(Some(replay)) => {
+                            return Trans::Push(Box::new(Game::replay(
+                                self.background_prefab.clone(),
+                                self.player_prefab.clone(),
+                                replay,
+                            )));
+                        }
+                        Ok(None) => warn!("There is no run to replay yet"),
+                        Err(e) => error!("{}", e),
+                    }
+                }
+                if ui::is_clicked(world, event, BUTTON_QUIT) {
+                    return Trans::Quit;
+                }
+            }
+            _ => {}
+        }
+        Trans::None
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some(game) = self.launch_game(data.world) {
+            return Trans::Push(Box::new(game));
+        }
+
+        let level = match self.levels.get(self.selected_level) {
+            Some(level) => format!("Level: {}", level),
+            None => "No levels found".to_string(),
+        };
+        ui::set_text(data.world, LEVEL_TEXT, &level);
+        let endless = if self.endless {
+            "Endless: On"
+        } else {
+            "Endless: Off"
+        };
+        ui::set_text(data.world, ENDLESS_TEXT, endless);
+        Trans::None
+    }
+}
+
+// Every RON file in assets/levels is a playable level
+fn find_levels() -> Vec<String> {
+    let levels_dir = application_root_dir()
+        .expect("Failed to find the application root")
+        .join("assets")
+        .join("levels");
+    let mut levels: Vec<String> = fs::read_dir(levels_dir)
+        .expect("Failed to read the levels directory")
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    levels.sort();
+    levels
+}
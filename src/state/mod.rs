@@ -1,4 +1,18 @@
+mod credits;
 mod game;
 mod loading;
+mod main_menu;
+mod paused;
+mod run_end;
+mod settings;
+mod ui;
 
-pub use self::{game::Game, loading::Loading};
+pub use self::{
+    credits::Credits,
+    game::{Game, RunStart},
+    loading::Loading,
+    main_menu::MainMenu,
+    paused::Paused,
+    run_end::{GameOver, RunSummary, Victory},
+    settings::Settings,
+};
@@ -0,0 +1,76 @@
+use crate::{
+    save,
+    state::{ui, Settings},
+};
+use amethyst::{
+    ecs::Entity,
+    input::{is_close_requested, is_key_down, VirtualKeyCode},
+    ui::UiCreator,
+    GameData, SimpleState, SimpleTrans, StateData, StateEvent, Trans,
+};
+
+const BUTTON_RESUME: &str = "resume_button";
+const BUTTON_SAVE: &str = "save_button";
+const BUTTON_SETTINGS: &str = "settings_button";
+const BUTTON_QUIT: &str = "quit_button";
+
+// Pushed on top of the game, which keeps being drawn (and its HUD updated) underneath. The
+// simulation only steps while the game is the top state, so it's frozen in the meantime
+#[derive(Default)]
+pub struct Paused {
+    ui_root: Option<Entity>,
+}
+
+impl SimpleState for Paused {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        self.ui_root =
+            Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/pause.ron", ())));
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        if let Some(ui_root) = self.ui_root.take() {
+            ui::delete_ui(world, ui_root);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let StateData { world, .. } = data;
+
+        match &event {
+            StateEvent::Window(event) => {
+                if is_close_requested(event) {
+                    save::save_run(world);
+                    return Trans::Quit;
+                }
+                if is_key_down(event, VirtualKeyCode::Escape) {
+                    return Trans::Pop;
+                }
+            }
+            StateEvent::Ui(event) => {
+                if ui::is_clicked(world, event, BUTTON_RESUME) {
+                    return Trans::Pop;
+                }
+                if ui::is_clicked(world, event, BUTTON_SAVE) {
+                    save::save_run(world);
+                }
+                if ui::is_clicked(world, event, BUTTON_SETTINGS) {
+                    return Trans::Push(Box::new(Settings::default()));
+                }
+                // The game sits right on top of the main menu, so popping both gets us back to it
+                if ui::is_clicked(world, event, BUTTON_QUIT) {
+                    return Trans::Sequence(vec![Trans::Pop, Trans::Pop]);
+                }
+            }
+            _ => {}
+        }
+        Trans::None
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{
+    resources::{EndlessWaves, RunStats, Score, WaveSchedule},
+    state::{ui, Game},
+};
+use amethyst::{
+    ecs::{Entity, WorldExt},
+    input::{is_close_requested, is_key_down, VirtualKeyCode},
+    prelude::World,
+    ui::UiCreator,
+    GameData, SimpleState, SimpleTrans, StateData, StateEvent, Trans,
+};
+use serde::Serialize;
+
+const BUTTON_RETRY: &str = "retry_button";
+const BUTTON_MENU: &str = "menu_button";
+const WAVES_TEXT: &str = "summary_waves_text";
+const SCORE_TEXT: &str = "summary_score_text";
+const KILLS_TEXT: &str = "summary_kills_text";
+const TOWERS_TEXT: &str = "summary_towers_text";
+const MONEY_TEXT: &str = "summary_money_text";
+const TIME_TEXT: &str = "summary_time_text";
+const LEADERBOARD_TEXT: &str = "leaderboard_text";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RunSummary {
+    pub waves_survived: usize,
+    pub total_waves: usize,
+    // Endless runs have no set number of waves
+    pub endless: bool,
+    pub score: u32,
+    pub stats: RunStats,
+}
+
+impl RunSummary {
+    // Sums up the run currently in the world
+    pub fn new(world: &World) -> Self {
+        let wave_schedule = world.read_resource::<WaveSchedule>();
+        RunSummary {
+            // The wave that's on when the last life is lost doesn't count
+            waves_survived: if wave_schedule.is_finished() {
+                wave_schedule.total_waves()
+            } else {
+                wave_schedule.wave_number() - 1
+            },
+            total_waves: wave_schedule.total_waves(),
+            endless: world.has_value::<EndlessWaves>(),
+            score: world.read_resource::<Score>().points,
+            stats: (*world.read_resource::<RunStats>()).clone(),
+        }
+    }
+}
+
+// Reached when the last life is lost
+pub struct GameOver {
+    end: RunEnd,
+}
+
+impl GameOver {
+    pub fn new(summary: RunSummary, retry: Game) -> Self {
+        GameOver {
+            end: RunEnd::new("ui/game_over.ron", summary, retry),
+        }
+    }
+
+    pub fn with_leaderboard(mut self, leaderboard: String) -> Self {
+        self.end.leaderboard = leaderboard;
+        self
+    }
+}
+
+// Reached when every wave has been cleared
+pub struct Victory {
+    end: RunEnd,
+}
+
+impl Victory {
+    pub fn new(summary: RunSummary, retry: Game) -> Self {
+        Victory {
+            end: RunEnd::new("ui/victory.ron", summary, retry),
+        }
+    }
+}
+
+impl SimpleState for GameOver {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.end.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.end.hide(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        self.end.handle_event(data.world, event)
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        self.end.update(data.world);
+        Trans::None
+    }
+}
+
+impl SimpleState for Victory {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.end.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.end.hide(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        self.end.handle_event(data.world, event)
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        self.end.update(data.world);
+        Trans::None
+    }
+}
+
+// Everything both end screens have in common, they only differ in the UI file they show
+struct RunEnd {
+    ui_file: &'static str,
+    summary: RunSummary,
+    // Handed over on retry, so that a new run can start right away
+    retry: Option<Game>,
+    // Only shown for endless runs
+    leaderboard: String,
+    ui_root: Option<Entity>,
+}
+
+impl RunEnd {
+    fn new(ui_file: &'static str, summary: RunSummary, retry: Game) -> Self {
+        RunEnd {
+            ui_file,
+            summary,
+            retry: Some(retry),
+            leaderboard: String::new(),
+            ui_root: None,
+        }
+    }
+
+    fn show(&mut self, world: &mut World) {
+        let ui_file = self.ui_file;
+        self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create(ui_file, ())));
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        if let Some(ui_root) = self.ui_root.take() {
+            ui::delete_ui(world, ui_root);
+        }
+    }
+
+    fn handle_event(&mut self, world: &mut World, event: StateEvent) -> SimpleTrans {
+        match &event {
+            StateEvent::Window(event) => {
+                if is_close_requested(event) {
+                    return Trans::Quit;
+                }
+                // The end screens replace the game, so popping them goes back to the main menu
+                if is_key_down(event, VirtualKeyCode::Escape) {
+                    return Trans::Pop;
+                }
+            }
+            StateEvent::Ui(event) => {
+                if ui::is_clicked(world, event, BUTTON_RETRY) {
+                    if let Some(game) = self.retry.take() {
+                        return Trans::Switch(Box::new(game));
+                    }
+                }
+                if ui::is_clicked(world, event, BUTTON_MENU) {
+                    return Trans::Pop;
+                }
+            }
+            _ => {}
+        }
+        Trans::None
+    }
+
+    // The UI is loaded in the background, so texts are filled in as soon as they show up
+    fn update(&self, world: &mut World) {
+        let summary = &self.summary;
+        let time_played = summary.stats.time_played as u32;
+        let waves = if summary.endless {
+            format!("Waves survived: {}", summary.waves_survived)
+        } else {
+            format!(
+                "Waves survived: {}/{}",
+                summary.waves_survived, summary.total_waves
+            )
+        };
+        ui::set_text(world, WAVES_TEXT, &waves);
+        ui::set_text(world, LEADERBOARD_TEXT, &self.leaderboard);
+        ui::set_text(world, SCORE_TEXT, &format!("Score: {}", summary.score));
+        ui::set_text(
+            world,
+            KILLS_TEXT,
+            &format!("Enemies killed: {}", summary.stats.kills),
+        );
+        ui::set_text(
+            world,
+            TOWERS_TEXT,
+            &format!("Towers built: {}", summary.stats.towers_built),
+        );
+        ui::set_text(
+            world,
+            MONEY_TEXT,
+            &format!("Money earned: {}", summary.stats.money_earned),
+        );
+        ui::set_text(
+            world,
+            TIME_TEXT,
+            &format!("Time played: {}:{:02}", time_played / 60, time_played % 60),
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{audio, state::ui};
+use amethyst::{
+    ecs::Entity,
+    input::{is_close_requested, is_key_down, VirtualKeyCode},
+    prelude::WorldExt,
+    ui::UiCreator,
+    GameData, SimpleState, SimpleTrans, StateData, StateEvent, Trans,
+};
+
+const BUTTON_MUSIC: &str = "music_button";
+const BUTTON_BACK: &str = "back_button";
+const MUSIC_TEXT: &str = "music_text";
+
+// Can be pushed on top of both the main menu and the pause menu
+#[derive(Default)]
+pub struct Settings {
+    ui_root: Option<Entity>,
+}
+
+impl SimpleState for Settings {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        self.ui_root =
+            Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/settings.ron", ())));
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if let Some(ui_root) = self.ui_root.take() {
+            ui::delete_ui(data.world, ui_root);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let StateData { world, .. } = data;
+
+        match &event {
+            StateEvent::Window(event) => {
+                if is_close_requested(event) {
+                    return Trans::Quit;
+                }
+                if is_key_down(event, VirtualKeyCode::Escape) {
+                    return Trans::Pop;
+                }
+            }
+            StateEvent::Ui(event) => {
+                if ui::is_clicked(world, event, BUTTON_MUSIC) {
+                    {
+                        let mut settings = world.write_resource::<audio::AudioSettings>();
+                        settings.music = !settings.music;
+                    }
+                    audio::apply_settings(world);
+                }
+                if ui::is_clicked(world, event, BUTTON_BACK) {
+                    return Trans::Pop;
+                }
+            }
+            _ => {}
+        }
+        Trans::None
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let music = if data.world.read_resource::<audio::AudioSettings>().music {
+            "Music: On"
+        } else {
+            "Music: Off"
+        };
+        ui::set_text(data.world, MUSIC_TEXT, music);
+        Trans::None
+    }
+}
@@ -0,0 +1,55 @@
+use amethyst::{
+    core::ParentHierarchy,
+    ecs::{Entity, Join, WriteStorage},
+    prelude::{World, WorldExt},
+    ui::{UiEvent, UiEventType, UiFinder, UiText, UiTransform},
+};
+
+// Whether the event is a click on the UI element with the given id. UI files are loaded in the
+// background, so elements can't be looked up once when the state starts
+pub fn is_clicked(world: &mut World, event: &UiEvent, id: &str) -> bool {
+    if event.event_type != UiEventType::Click {
+        return false;
+    }
+    world.exec(|finder: UiFinder<'_>| finder.find(id)) == Some(event.target)
+}
+
+// Deletes a UI tree created through `UiCreator`, children included
+pub fn delete_ui(world: &mut World, root: Entity) {
+    let children: Vec<Entity> = {
+        let hierarchy = world.read_resource::<ParentHierarchy>();
+        let entities = world.entities();
+        (&entities, &hierarchy.all_children(root))
+            .join()
+            .map(|(entity, _)| entity)
+            .collect()
+    };
+    world
+        .delete_entities(&children)
+        .expect("Failed to delete UI elements");
+    world.delete_entity(root).expect("Failed to delete UI root");
+}
+
+// Does nothing if the element hasn't been loaded yet, so it's meant to be called every frame
+pub fn set_text(world: &mut World, id: &str, text: &str) {
+    world.exec(
+        |(finder, mut ui_texts): (UiFinder<'_>, WriteStorage<'_, UiText>)| {
+            if let Some(ui_text) = finder.find(id).and_then(|e| ui_texts.get_mut(e)) {
+                if ui_text.text != text {
+                    ui_text.text = text.to_string();
+                }
+            }
+        },
+    );
+}
+
+// Same as `set_text`, for the width of an element
+pub fn set_width(world: &mut World, id: &str, width: f32) {
+    world.exec(
+        |(finder, mut ui_transforms): (UiFinder<'_>, WriteStorage<'_, UiTransform>)| {
+            if let Some(ui_transform) = finder.find(id).and_then(|e| ui_transforms.get_mut(e)) {
+                ui_transform.width = width;
+            }
+        },
+    );
+}
@@ -1,5 +1,6 @@
 use crate::{
-    components::{Bullet, Hitable, Velocity},
+    components::{Bounty, Bullet, Hitable, Velocity},
+    resources::{Economy, GameplayConfig, RunStats, Score},
     util,
 };
 use amethyst::{
@@ -19,11 +20,27 @@ impl<'s> System<'s> for BulletSystem {
         WriteStorage<'s, Velocity>,
         WriteStorage<'s, Bullet>,
         WriteStorage<'s, Hitable>,
+        ReadStorage<'s, Bounty>,
+        Option<Write<'s, Economy>>,
+        Option<Write<'s, Score>>,
+        Option<Write<'s, RunStats>>,
+        Read<'s, GameplayConfig>,
     );
 
     fn run(
         &mut self,
-        (entities, transforms, mut velocities, mut bullets, mut hitable): Self::SystemData,
+        (
+            entities,
+            transforms,
+            mut velocities,
+            mut bullets,
+            mut hitable,
+            bounties,
+            mut economy,
+            mut score,
+            mut run_stats,
+            gameplay,
+        ): Self::SystemData,
     ) {
         let hitables: Vec<_> = (&entities, &transforms, &hitable)
             .join()
@@ -56,14 +73,31 @@ impl<'s> System<'s> for BulletSystem {
                             },
                         ) {
                             if let Some(ref mut hitable) = hitable.get_mut(*hitable_entity) {
+                                // Deletions only happen at the end of the frame, so a second
+                                // bullet could still hit an enemy that was just killed
+                                let was_alive = hitable.health > 0.;
                                 hitable.health -= bullet.hitpoints;
                                 entities
                                     .delete(bullet_entity)
                                     .expect("Issue deleting bullet");
-                                if hitable.health <= 0. {
+                                if was_alive && hitable.health <= 0. {
                                     entities
                                         .delete(*hitable_entity)
                                         .expect("Issue deleting enemy from bullet");
+                                    if let Some(ref mut run_stats) = run_stats {
+                                        run_stats.kills += 1;
+                                    }
+                                    if let Some(bounty) = bounties.get(*hitable_entity) {
+                                        if let Some(ref mut economy) = economy {
+                                            economy.earn(bounty.money);
+                                        }
+                                        if let Some(ref mut score) = score {
+                                            score.points += bounty.score;
+                                        }
+                                        if let Some(ref mut run_stats) = run_stats {
+                                            run_stats.money_earned += bounty.money;
+                                        }
+                                    }
                                 }
                                 deleted_bullet = true;
                                 break;
@@ -82,8 +116,8 @@ impl<'s> System<'s> for BulletSystem {
 
             let position = Point2::new(transform.translation().x, transform.translation().y);
 
-            // At a distance of 1000, we've gone waaaay out of the screen. Delet
-            if (position - Point2::new(0., 0.)).norm() > 1000. {
+            // At this distance, we've gone waaaay out of the screen. Delet
+            if (position - Point2::new(0., 0.)).norm() > gameplay.bullet_despawn_radius {
                 entities
                     .delete(bullet_entity)
                     .expect("Issue deleting bullet");
@@ -93,3 +127,59 @@ impl<'s> System<'s> for BulletSystem {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::Hitable,
+        harness::Harness,
+        input::TickInput,
+        resources::{Economy, RunStats},
+    };
+    use amethyst::ecs::WorldExt;
+    use nalgebra::Vector2;
+
+    #[test]
+    fn bullet_fired_at_an_enemy_reduces_its_health() {
+        let mut harness = Harness::new("level1");
+        let enemy_position = harness.cell((17, 8));
+        let enemy = harness.spawn_enemy(enemy_position, "south");
+        harness.add_tower(enemy_position - Vector2::new(100., 0.), true);
+        let aim = TickInput {
+            mouse: Some(enemy_position),
+            ..Default::default()
+        };
+        harness.step(aim.clone());
+
+        harness.tap(&aim, |input| input.fire = true);
+        harness.run(20, &aim);
+
+        let hitables = harness.world.read_storage::<Hitable>();
+        let hitable = hitables.get(enemy).expect("Enemy is gone");
+        assert_eq!(hitable.health, 75.);
+    }
+
+    #[test]
+    fn killing_an_enemy_pays_its_bounty() {
+        let mut harness = Harness::new("level1");
+        let enemy_position = harness.cell((17, 8));
+        let enemy = harness.spawn_enemy(enemy_position, "south");
+        harness.add_tower(enemy_position - Vector2::new(100., 0.), true);
+        let money = harness.world.read_resource::<Economy>().money;
+        harness.step(TickInput::default());
+
+        // Four shots, aimed wherever the enemy has moved to
+        for _ in 0..4 {
+            let aim = TickInput {
+                mouse: Some(harness.position(enemy)),
+                ..Default::default()
+            };
+            harness.tap(&aim, |input| input.fire = true);
+            harness.run(20, &aim);
+        }
+
+        assert!(!harness.world.is_alive(enemy));
+        assert_eq!(harness.world.read_resource::<RunStats>().kills, 1);
+        assert_eq!(harness.world.read_resource::<Economy>().money, money + 10);
+    }
+}
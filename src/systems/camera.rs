@@ -1,4 +1,7 @@
-use crate::{resources::FollowedObject, util};
+use crate::{
+    resources::{FollowedObject, GameplayConfig},
+    util,
+};
 use amethyst::{
     core::Transform,
     derive::SystemDesc,
@@ -17,11 +20,12 @@ impl<'s> System<'s> for CameraFollowSystem {
         ReadStorage<'s, Camera>,
         Option<Read<'s, FollowedObject>>,
         Read<'s, ActiveCamera>,
+        Read<'s, GameplayConfig>,
     );
 
     fn run(
         &mut self,
-        (entities, mut transforms, cameras, followed_object, active_camera): Self::SystemData,
+        (entities, mut transforms, cameras, followed_object, active_camera, gameplay): Self::SystemData,
     ) {
         // Only continue if we have an active camera and a followed object with a transform
         let followed_object = match followed_object {
@@ -59,6 +63,7 @@ impl<'s> System<'s> for CameraFollowSystem {
             camera_position,
             followed_position,
             followed_object.hard_lock,
+            gameplay.camera_dead_zone,
         );
         mut_camera_transform.x = camera_position.x;
         mut_camera_transform.y = camera_position.y;
@@ -71,6 +76,7 @@ impl CameraFollowSystem {
         camera_position: Point2<f32>,
         followed_position: Point2<f32>,
         hard_lock: bool,
+        (dead_x, dead_y): (f32, f32),
     ) -> Point2<f32> {
         if hard_lock {
             followed_position
@@ -82,20 +88,20 @@ impl CameraFollowSystem {
 
             let dir = camera_position - followed_position;
             // There's 4 lines our segment can intersect with:
-            // y = followed_position.y + 68
-            // y = followed_position.y - 68
-            // x = followed_position.x + 120
-            // x = followed_position.x - 120
+            // y = followed_position.y + dead_y
+            // y = followed_position.y - dead_y
+            // x = followed_position.x + dead_x
+            // x = followed_position.x - dead_x
             //
             // We're looking for the line with the intersection closest to the player, in the
             // direction of the camera, only including ones between the camera and the person (to
             // avoid moving the camera away from the player). If none is found, we're already in a
             // suitable position and we can keep the camera as is
             [
-                (followed_position.y + 68., false),
-                (followed_position.y - 68., false),
-                (followed_position.x + 120., true),
-                (followed_position.x - 120., true),
+                (followed_position.y + dead_y, false),
+                (followed_position.y - dead_y, false),
+                (followed_position.x + dead_x, true),
+                (followed_position.x - dead_x, true),
             ]
             .iter()
             .filter_map(|(c, vert)| util::intersect(followed_position, dir, *c, *vert))
@@ -1,17 +1,15 @@
 use crate::{
     components::{ControlledCharacter, Tower, TowerDirection, Velocity},
-    input::{self, ActionBinding, AxisBinding, GameBindingTypes},
+    input::TickInput,
     prefabs::BulletPrefab,
-    resources::{BulletPrefabSet, BulletType, FollowedObject},
+    resources::{BulletPrefabSet, BulletType, FollowedObject, GameplayConfig},
 };
 use amethyst::{
     assets::{Handle, Prefab},
     core::Transform,
     derive::SystemDesc,
-    ecs::{prelude::*, Entities, Read, ReadExpect, ReadStorage, System, WriteStorage},
-    input::InputHandler,
-    renderer::{sprite::SpriteRender, ActiveCamera, Camera},
-    window::ScreenDimensions,
+    ecs::{prelude::*, Entities, Read, ReadStorage, System, WriteStorage},
+    renderer::sprite::SpriteRender,
 };
 use log::info;
 use nalgebra::{Point2, Vector2};
@@ -24,15 +22,13 @@ pub struct ShooterControlSystem {
 impl<'s> System<'s> for ShooterControlSystem {
     type SystemData = (
         Entities<'s>,
-        Read<'s, InputHandler<GameBindingTypes>>,
+        Read<'s, TickInput>,
         WriteStorage<'s, Transform>,
         WriteStorage<'s, Tower>,
         WriteStorage<'s, Velocity>,
         WriteStorage<'s, Handle<Prefab<BulletPrefab>>>,
         Read<'s, BulletPrefabSet>,
-        ReadStorage<'s, Camera>,
-        Read<'s, ActiveCamera>,
-        ReadExpect<'s, ScreenDimensions>,
+        Read<'s, GameplayConfig>,
     );
 
     fn run(
@@ -45,20 +41,10 @@ impl<'s> System<'s> for ShooterControlSystem {
             mut velocities,
             mut bullet_prefabs,
             bullet_prefab_set,
-            cameras,
-            active_camera,
-            screen_dimensions,
+            gameplay,
         ): Self::SystemData,
     ) {
-        self.point_routine(
-            &entities,
-            &input,
-            &transforms,
-            &mut towers,
-            &cameras,
-            &active_camera,
-            &screen_dimensions,
-        );
+        self.point_routine(&input, &transforms, &mut towers);
         self.fire_routine(
             &entities,
             &input,
@@ -67,6 +53,7 @@ impl<'s> System<'s> for ShooterControlSystem {
             &towers,
             &mut bullet_prefabs,
             &bullet_prefab_set,
+            &gameplay,
         );
     }
 }
@@ -74,22 +61,11 @@ impl<'s> System<'s> for ShooterControlSystem {
 impl ShooterControlSystem {
     fn point_routine<'s>(
         &mut self,
-        entities: &Entities<'s>,
-        input: &Read<'s, InputHandler<GameBindingTypes>>,
+        input: &Read<'s, TickInput>,
         transforms: &WriteStorage<'s, Transform>,
         towers: &mut WriteStorage<'s, Tower>,
-        cameras: &ReadStorage<'s, Camera>,
-        active_camera: &Read<'s, ActiveCamera>,
-        screen_dimensions: &ReadExpect<'s, ScreenDimensions>,
     ) {
-        let mouse = match input::get_mouse_projection(
-            entities,
-            input,
-            transforms,
-            cameras,
-            active_camera,
-            screen_dimensions,
-        ) {
+        let mouse = match input.mouse {
             Some(m) => m,
             None => return,
         };
@@ -111,23 +87,25 @@ impl ShooterControlSystem {
     fn fire_routine<'s>(
         &mut self,
         entities: &Entities<'s>,
-        input: &Read<'s, InputHandler<GameBindingTypes>>,
+        input: &Read<'s, TickInput>,
         transforms: &mut WriteStorage<'s, Transform>,
         velocities: &mut WriteStorage<'s, Velocity>,
         towers: &WriteStorage<'s, Tower>,
         bullet_prefabs: &mut WriteStorage<'s, Handle<Prefab<BulletPrefab>>>,
         bullet_prefab_set: &Read<'s, BulletPrefabSet>,
+        gameplay: &GameplayConfig,
     ) {
-        let fire_is_pressed = input.action_is_down(&ActionBinding::Fire).unwrap_or(false);
+        let fire_is_pressed = input.fire;
 
         if !fire_is_pressed && self.fire_was_pressed {
             let mut tower_data: Vec<(Vector2<f32>, Vector2<f32>)> = vec![];
             for (transform, tower) in (&*transforms, towers).join() {
                 if tower.active {
                     let translation = transform.translation().clone();
+                    // Bullets come out a bit above the tower's centre
                     tower_data.push((
                         tower.dir.clone(),
-                        Vector2::new(translation.x, translation.y),
+                        Vector2::new(translation.x, translation.y + gameplay.bullet_spawn_offset),
                     ));
                 }
             }
@@ -199,12 +177,13 @@ pub struct PlayerControlSystem {
 impl<'s> System<'s> for PlayerControlSystem {
     type SystemData = (
         Entities<'s>,
-        Read<'s, InputHandler<GameBindingTypes>>,
+        Read<'s, TickInput>,
         WriteStorage<'s, ControlledCharacter>,
         WriteStorage<'s, Tower>,
         WriteStorage<'s, Transform>,
         WriteStorage<'s, Velocity>,
         Option<Write<'s, FollowedObject>>,
+        Read<'s, GameplayConfig>,
     );
 
     fn run(
@@ -217,14 +196,13 @@ impl<'s> System<'s> for PlayerControlSystem {
             mut transforms,
             mut velocities,
             mut followed_object,
+            gameplay,
         ): Self::SystemData,
     ) {
         // Move according to this frame
         for (controlled_character, velocity) in (&controlled_characters, &mut velocities).join() {
             if !controlled_character.locked {
-                let forwards = input.axis_value(&AxisBinding::Forwards).unwrap_or(0.0);
-                let sideways = input.axis_value(&AxisBinding::Sideways).unwrap_or(0.0);
-                let mut direction = Vector2::new(sideways, forwards);
+                let mut direction = Vector2::new(input.sideways, input.forwards);
                 if direction.norm() != 0.0 {
                     direction = direction.normalize();
                 }
@@ -233,9 +211,7 @@ impl<'s> System<'s> for PlayerControlSystem {
             }
         }
 
-        let activate_is_pressed = input
-            .action_is_down(&ActionBinding::Activate)
-            .unwrap_or(false);
+        let activate_is_pressed = input.activate;
 
         // Try to enter if requested
         if !activate_is_pressed && self.activate_was_pressed {
@@ -262,10 +238,14 @@ impl<'s> System<'s> for PlayerControlSystem {
                 // If the character is locked, find the corresponding active tower and disable +
                 // exit
                 if controlled_character.locked {
-                    // Check within 1 unit as we should be right on top of it
+                    // Check within a tiny radius as we should be right on top of it
                     let closest_tower = match tower_data
                         .iter()
-                        .filter(|td| td.active && (td.pos - character_position).norm() <= 1.)
+                        .filter(|td| {
+                            td.active
+                                && (td.pos - character_position).norm()
+                                    <= gameplay.tower_exit_radius
+                        })
                         .min_by(|a, b| {
                             let a_dist = (a.pos - character_position).norm();
                             let b_dist = (b.pos - character_position).norm();
@@ -285,11 +265,15 @@ impl<'s> System<'s> for PlayerControlSystem {
                     transform.set_translation_xyz(closest_tower.pos.x, closest_tower.pos.y, 0.6);
                 }
                 // If the character is not locked, check if there's any inactive nearby towers
-                // (within (within 60. units) and enter them
+                // (within the enter radius) and enter them
                 else {
                     let closest_tower = match tower_data
                         .iter()
-                        .filter(|td| !td.active && (td.pos - character_position).norm() <= 60.)
+                        .filter(|td| {
+                            !td.active
+                                && (td.pos - character_position).norm()
+                                    <= gameplay.tower_enter_radius
+                        })
                         .min_by(|a, b| {
                             let a_dist = (a.pos - character_position).norm();
                             let b_dist = (b.pos - character_position).norm();
@@ -322,3 +306,100 @@ impl<'s> System<'s> for PlayerControlSystem {
         self.activate_was_pressed = activate_is_pressed;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::{ControlledCharacter, Tower},
+        harness::Harness,
+        input::TickInput,
+    };
+    use amethyst::ecs::WorldExt;
+    use nalgebra::Vector2;
+
+    #[test]
+    fn entering_a_tower_locks_the_player() {
+        let mut harness = Harness::new("level1");
+        let position = harness.cell((5, 10));
+        let player = harness.spawn_player(position);
+        let tower = harness.add_tower(position + Vector2::new(40., 0.), false);
+        harness.step(TickInput::default());
+
+        harness.tap(&TickInput::default(), |input| input.activate = true);
+
+        assert!(
+            harness
+                .world
+                .read_storage::<ControlledCharacter>()
+                .get(player)
+                .unwrap()
+                .locked
+        );
+        assert!(
+            harness
+                .world
+                .read_storage::<Tower>()
+                .get(tower)
+                .unwrap()
+                .active
+        );
+        assert_eq!(harness.position(tower), harness.position(player));
+    }
+
+    #[test]
+    fn leaving_a_tower_unlocks_the_player() {
+        let mut harness = Harness::new("level1");
+        let position = harness.cell((5, 10));
+        let player = harness.spawn_player(position);
+        let tower = harness.add_tower(position + Vector2::new(40., 0.), false);
+        harness.step(TickInput::default());
+
+        harness.tap(&TickInput::default(), |input| input.activate = true);
+        harness.tap(&TickInput::default(), |input| input.activate = true);
+
+        assert!(
+            !harness
+                .world
+                .read_storage::<ControlledCharacter>()
+                .get(player)
+                .unwrap()
+                .locked
+        );
+        assert!(
+            !harness
+                .world
+                .read_storage::<Tower>()
+                .get(tower)
+                .unwrap()
+                .active
+        );
+    }
+
+    #[test]
+    fn towers_out_of_reach_cannot_be_entered() {
+        let mut harness = Harness::new("level1");
+        let position = harness.cell((5, 10));
+        let player = harness.spawn_player(position);
+        let tower = harness.add_tower(position + Vector2::new(100., 0.), false);
+        harness.step(TickInput::default());
+
+        harness.tap(&TickInput::default(), |input| input.activate = true);
+
+        assert!(
+            !harness
+                .world
+                .read_storage::<ControlledCharacter>()
+                .get(player)
+                .unwrap()
+                .locked
+        );
+        assert!(
+            !harness
+                .world
+                .read_storage::<Tower>()
+                .get(tower)
+                .unwrap()
+                .active
+        );
+    }
+}
@@ -1,7 +1,11 @@
 use crate::{
-    components::{Guided, Velocity},
+    components::{Destination, EnemyKind, Guided, Restore, Velocity},
+    input::TickInput,
     prefabs::EnemyPrefab,
-    resources::{EnemyPrefabSet, EnemySpawning, EnemyType},
+    resources::{
+        Economy, EndlessWaves, EnemyPrefabSet, GameplayConfig, LevelMap, Lives, RunStats,
+        SimulationClock, WaveSchedule,
+    },
 };
 use amethyst::{
     assets::{Handle, Prefab},
@@ -9,41 +13,150 @@ use amethyst::{
     derive::SystemDesc,
     ecs::{prelude::*, Entities, Read, ReadStorage, System, WriteStorage},
 };
-use log::info;
-use nalgebra::Point2;
-use std::time::{Duration, Instant};
+use log::{info, warn};
+use nalgebra::{Point2, Vector2};
 
 #[derive(Default, SystemDesc)]
 pub struct EnemySpawnSystem {
-    last_enemy_spawn: Option<Instant>,
+    call_was_pressed: bool,
 }
 
 impl<'s> System<'s> for EnemySpawnSystem {
     type SystemData = (
         Entities<'s>,
         WriteStorage<'s, Handle<Prefab<EnemyPrefab>>>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, Destination>,
+        WriteStorage<'s, EnemyKind>,
+        WriteStorage<'s, Restore>,
         Read<'s, EnemyPrefabSet>,
-        Option<Read<'s, EnemySpawning>>,
+        Option<Write<'s, WaveSchedule>>,
+        Option<Read<'s, EndlessWaves>>,
+        Option<Read<'s, LevelMap>>,
+        Option<Write<'s, Economy>>,
+        Option<Write<'s, RunStats>>,
+        Read<'s, SimulationClock>,
+        Read<'s, GameplayConfig>,
+        Read<'s, TickInput>,
     );
 
     fn run(
         &mut self,
-        (entities, mut enemy_prefabs, enemy_prefab_set, enemy_spawning): Self::SystemData,
+        (
+            entities,
+            mut enemy_prefabs,
+            mut transforms,
+            mut destinations,
+            mut enemy_kinds,
+            mut restores,
+            enemy_prefab_set,
+            wave_schedule,
+            endless,
+            level_map,
+            economy,
+            run_stats,
+            clock,
+            gameplay,
+            input,
+        ): Self::SystemData,
     ) {
-        // Only spawn enemies once this exists
-        if let Some(_) = enemy_spawning {
-            let spawn_time = match self.last_enemy_spawn {
-                Some(i) => i.elapsed() > Duration::from_secs_f32(2.0),
-                None => true,
-            };
-
-            if spawn_time {
-                info!("Spawn!");
-                enemy_prefab_set
-                    .add_enemy(EnemyType::Standard, &entities, &mut enemy_prefabs)
-                    .expect("There was an issue spawning an enemy");
-                self.last_enemy_spawn = Some(Instant::now());
+        // Only spawn enemies once there's a level with waves to go through
+        let (mut wave_schedule, level_map) = match (wave_schedule, level_map) {
+            (Some(w), Some(l)) => (w, l),
+            _ => {
+                return;
             }
+        };
+
+        // Endless runs never run out of waves
+        if let Some(endless) = endless {
+            if wave_schedule.is_finished() {
+                let total = wave_schedule.total_waves() + 1;
+                endless.fill(&mut wave_schedule, &gameplay.endless, total);
+            }
+        }
+
+        // Calling a wave early pays for however long was left to wait for it
+        let call_is_pressed = input.call_wave;
+        if !call_is_pressed && self.call_was_pressed {
+            if let Some(skipped) = wave_schedule.call_next() {
+                let bonus = (skipped * gameplay.early_call_bonus) as u32;
+                info!("Wave called {:.1}s early, for {} money", skipped, bonus);
+                if let Some(mut economy) = economy {
+                    economy.earn(bonus);
+                }
+                if let Some(mut run_stats) = run_stats {
+                    run_stats.money_earned += bonus;
+                }
+            }
+        }
+        self.call_was_pressed = call_is_pressed;
+
+        for order in wave_schedule.advance(clock.step, gameplay.spawn_interval) {
+            info!("Spawn!");
+            // Both of these are checked when loading the level
+            let spawn = level_map.spawn(&order.spawn).expect("Unknown spawn");
+            let exit = level_map.exit(&order.exit).expect("Unknown exit");
+            let enemy = enemy_prefab_set
+                .add_enemy(
+                    order.enemy,
+                    level_map.cell_to_world(spawn),
+                    exit,
+                    &entities,
+                    &mut enemy_prefabs,
+                    &mut transforms,
+                    &mut destinations,
+                    &mut enemy_kinds,
+                )
+                .expect("There was an issue spawning an enemy");
+            if order.health != 1. {
+                restores
+                    .insert(
+                        enemy,
+                        Restore::Toughened {
+                            factor: order.health,
+                        },
+                    )
+                    .expect("Failed to toughen an enemy");
+            }
+        }
+    }
+}
+
+#[derive(SystemDesc)]
+pub struct PathfindingSystem;
+
+impl<'s> System<'s> for PathfindingSystem {
+    type SystemData = (
+        WriteStorage<'s, Guided>,
+        ReadStorage<'s, Destination>,
+        ReadStorage<'s, Transform>,
+        Option<Read<'s, LevelMap>>,
+    );
+
+    fn run(&mut self, (mut guided, destinations, transforms, level_map): Self::SystemData) {
+        let level_map = match level_map {
+            Some(l) => l,
+            None => {
+                return;
+            }
+        };
+
+        // Only recompute paths that were calculated on an older version of the map
+        for (guided, destination, transform) in (&mut guided, &destinations, &transforms).join() {
+            if guided.map_revision == Some(level_map.revision()) {
+                continue;
+            }
+
+            let position = Point2::new(transform.translation().x, transform.translation().y);
+            guided.path = level_map
+                .path_to(position, destination.exit)
+                .unwrap_or_else(|| {
+                    warn!("No path to the exit from {:?}", position);
+                    vec![]
+                });
+            guided.curr_waypoint = 0;
+            guided.map_revision = Some(level_map.revision());
         }
     }
 }
@@ -57,32 +170,135 @@ impl<'s> System<'s> for EnemyMovementSystem {
         WriteStorage<'s, Velocity>,
         WriteStorage<'s, Guided>,
         ReadStorage<'s, Transform>,
+        Option<Write<'s, Lives>>,
     );
 
-    fn run(&mut self, (entities, mut velocities, mut guided, transforms): Self::SystemData) {
+    fn run(
+        &mut self,
+        (entities, mut velocities, mut guided, transforms, mut lives): Self::SystemData,
+    ) {
         for (entity, velocity, guided, transform) in
             (&entities, &mut velocities, &mut guided, &transforms).join()
         {
+            // Without a path (either not computed yet or there's no way to the exit) just wait
+            if guided.path.is_empty() {
+                velocity.v = Vector2::new(0.0, 0.0);
+                continue;
+            }
+
             let position = Point2::new(transform.translation().x, transform.translation().y);
             // You are within reasonable distance of the waypoint, switch waypoints
-            if (guided.waypoints[guided.curr_waypoint] - position).norm() < guided.speed / 10. {
+            if (guided.path[guided.curr_waypoint] - position).norm() < guided.speed / 10. {
                 guided.curr_waypoint += 1;
             }
 
-            // If you've reached the end, delet the enemy and skip all other operations here
-            if guided.curr_waypoint >= guided.waypoints.len() {
+            // If you've reached the end, delet the enemy, take a life away, and skip all other
+            // operations here
+            if guided.curr_waypoint >= guided.path.len() {
                 entities.delete(entity).expect("Issue deleting enemy");
+                if let Some(ref mut lives) = lives {
+                    lives.remaining = lives.remaining.saturating_sub(1);
+                }
                 continue;
             }
 
             // Just in case, if you're on top of the waypoint, just skip this iteration
             // and keep on moving in the direction you were going
-            if position == guided.waypoints[guided.curr_waypoint] {
+            if position == guided.path[guided.curr_waypoint] {
                 continue;
             }
 
-            let dir = (guided.waypoints[guided.curr_waypoint] - position).normalize();
+            let dir = (guided.path[guided.curr_waypoint] - position).normalize();
             velocity.v = dir * guided.speed;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::Destination,
+        harness::Harness,
+        input::TickInput,
+        resources::{
+            Economy, EndlessWaves, EnemyType, GameplayConfig, Lives, Wave, WaveGroup, WaveSchedule,
+            SIMULATION_STEP,
+        },
+        simulation,
+    };
+    use amethyst::ecs::{Join, WorldExt};
+
+    #[test]
+    fn enemy_reaching_the_exit_takes_a_life() {
+        let mut harness = Harness::new("level1");
+        let lives = harness.world.read_resource::<Lives>().remaining;
+        let enemy_position = harness.cell((27, 3));
+        let enemy = harness.spawn_enemy(enemy_position, "south");
+
+        // Three cells to go, at 50 units a second
+        harness.run(150, &TickInput::default());
+
+        assert!(!harness.world.is_alive(enemy));
+        assert_eq!(harness.world.read_resource::<Lives>().remaining, lives - 1);
+    }
+
+    #[test]
+    fn calling_a_wave_early_pays_a_bonus() {
+        let mut harness = Harness::new("level1");
+        harness.world.insert(WaveSchedule::new(vec![Wave {
+            delay: 10.,
+            groups: vec![WaveGroup {
+                enemy: EnemyType::Standard,
+                count: 1,
+                interval: None,
+                health: None,
+                spawn: "north".to_string(),
+                exit: "south".to_string(),
+            }],
+        }]));
+        let money = harness.world.read_resource::<Economy>().money;
+        let bonus = harness
+            .world
+            .read_resource::<GameplayConfig>()
+            .early_call_bonus;
+
+        harness.tap(&TickInput::default(), |input| input.call_wave = true);
+        harness.step(TickInput::default());
+
+        assert_eq!(
+            harness.world.read_storage::<Destination>().join().count(),
+            1
+        );
+        // The wave was waited on for a tick before being called
+        let skipped = 10. - SIMULATION_STEP;
+        assert_eq!(
+            harness.world.read_resource::<Economy>().money,
+            money + (skipped * bonus) as u32
+        );
+    }
+
+    #[test]
+    fn endless_runs_keep_going() {
+        let mut harness = Harness::new("level1");
+        let schedule = WaveSchedule::new(vec![Wave {
+            delay: 0.,
+            groups: vec![WaveGroup {
+                enemy: EnemyType::Standard,
+                count: 1,
+                interval: None,
+                health: None,
+                spawn: "north".to_string(),
+                exit: "south".to_string(),
+            }],
+        }]);
+        harness.world.insert(EndlessWaves::new(1, &schedule));
+        harness.world.insert(schedule);
+
+        harness.run(2, &TickInput::default());
+        let schedule = harness.world.read_resource::<WaveSchedule>();
+        assert_eq!(schedule.total_waves(), 2);
+        assert!(!schedule.is_finished());
+        drop(schedule);
+        assert_eq!(simulation::outcome(&harness.world), None);
+    }
+}
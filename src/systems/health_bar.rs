@@ -0,0 +1,93 @@
+use crate::{
+    components::{HealthBar, Hitable},
+    prefabs::HealthBarPrefab,
+    resources::{HealthBarPrefabSet, HEALTH_BAR_WIDTH},
+};
+use amethyst::{
+    assets::{Handle, Prefab},
+    core::{Hidden, Parent, Transform},
+    derive::SystemDesc,
+    ecs::{prelude::*, Entities, Read, ReadStorage, System, WriteStorage},
+};
+use std::collections::HashSet;
+
+#[derive(SystemDesc)]
+pub struct HealthBarSystem;
+
+impl<'s> System<'s> for HealthBarSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Hitable>,
+        WriteStorage<'s, HealthBar>,
+        WriteStorage<'s, Handle<Prefab<HealthBarPrefab>>>,
+        WriteStorage<'s, Parent>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, Hidden>,
+        Read<'s, HealthBarPrefabSet>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            hitables,
+            mut health_bars,
+            mut health_bar_prefabs,
+            mut parents,
+            mut transforms,
+            mut hiddens,
+            health_bar_prefab_set,
+        ): Self::SystemData,
+    ) {
+        // Update existing bars, getting rid of the ones whose owner is gone
+        let mut owners = HashSet::new();
+        for (entity, health_bar, transform) in (&entities, &health_bars, &mut transforms).join() {
+            let hitable = match hitables.get(health_bar.owner) {
+                Some(h) if entities.is_alive(health_bar.owner) => h,
+                _ => {
+                    entities.delete(entity).expect("Issue deleting health bar");
+                    continue;
+                }
+            };
+            owners.insert(health_bar.owner);
+
+            // Scale the bar down, keeping it aligned to the left. Without any max health there's
+            // nothing to show a share of, so the bar stays hidden
+            let ratio = if hitable.max_health > 0. {
+                (hitable.health / hitable.max_health).clamp(0., 1.)
+            } else {
+                1.
+            };
+            transform.set_scale([ratio, 1., 1.].into());
+            transform.set_translation_x(-HEALTH_BAR_WIDTH * (1. - ratio) / 2.);
+
+            // No need to show the bar until the owner has taken damage
+            if ratio >= 1. {
+                hiddens
+                    .insert(entity, Hidden)
+                    .expect("Issue hiding health bar");
+            } else {
+                hiddens.remove(entity);
+            }
+        }
+
+        // Add bars to anything hitable that doesn't have one yet
+        let missing: Vec<_> = (&entities, &hitables)
+            .join()
+            .map(|(entity, _)| entity)
+            .filter(|entity| !owners.contains(entity))
+            .collect();
+        for owner in missing {
+            health_bar_prefab_set
+                .add_health_bar(
+                    owner,
+                    &entities,
+                    &mut health_bar_prefabs,
+                    &mut health_bars,
+                    &mut parents,
+                    &mut transforms,
+                )
+                .expect("Failed to add health bar");
+        }
+    }
+}
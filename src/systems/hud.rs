@@ -0,0 +1,111 @@
+use crate::{
+    components::Destination,
+    resources::{Economy, EndlessWaves, GameRng, GameSpeed, Lives, Score, WaveSchedule},
+};
+use amethyst::{
+    derive::SystemDesc,
+    ecs::{Join, Read, ReadStorage, System, SystemData, WriteStorage},
+    ui::{UiFinder, UiText},
+};
+
+const MONEY_TEXT: &str = "money_text";
+const LIVES_TEXT: &str = "lives_text";
+const WAVE_TEXT: &str = "wave_text";
+const SCORE_TEXT: &str = "score_text";
+const ENEMIES_TEXT: &str = "enemies_text";
+const SEED_TEXT: &str = "seed_text";
+// Amethyst names the text of a button after the button
+const SPEED_TEXT: &str = "speed_button_btn_txt";
+
+#[derive(SystemDesc)]
+pub struct HudSystem;
+
+impl<'s> System<'s> for HudSystem {
+    type SystemData = (
+        UiFinder<'s>,
+        WriteStorage<'s, UiText>,
+        ReadStorage<'s, Destination>,
+        Option<Read<'s, Economy>>,
+        Option<Read<'s, Lives>>,
+        Option<Read<'s, Score>>,
+        Option<Read<'s, WaveSchedule>>,
+        Option<Read<'s, GameSpeed>>,
+        Option<Read<'s, EndlessWaves>>,
+        Option<Read<'s, GameRng>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            ui_finder,
+            mut ui_texts,
+            destinations,
+            economy,
+            lives,
+            score,
+            wave_schedule,
+            speed,
+            endless,
+            rng,
+        ): Self::SystemData,
+    ) {
+        if let Some(economy) = economy {
+            set_text(&ui_finder, &mut ui_texts, MONEY_TEXT, || {
+                format!("Money: {}", economy.money)
+            });
+        }
+        if let Some(lives) = lives {
+            set_text(&ui_finder, &mut ui_texts, LIVES_TEXT, || {
+                format!("Lives: {}", lives.remaining)
+            });
+        }
+        if let Some(score) = score {
+            set_text(&ui_finder, &mut ui_texts, SCORE_TEXT, || {
+                format!("Score: {}", score.points)
+            });
+        }
+        if let Some(wave_schedule) = wave_schedule {
+            set_text(&ui_finder, &mut ui_texts, WAVE_TEXT, || {
+                // Endless runs have no last wave
+                if endless.is_some() {
+                    format!("Wave: {}", wave_schedule.wave_number())
+                } else {
+                    format!(
+                        "Wave: {}/{}",
+                        wave_schedule.wave_number(),
+                        wave_schedule.total_waves()
+                    )
+                }
+            });
+            // Enemies still on their way to an exit, plus the ones yet to come out of a spawn
+            let on_map = destinations.join().count();
+            set_text(&ui_finder, &mut ui_texts, ENEMIES_TEXT, || {
+                format!("Enemies: {}", on_map + wave_schedule.remaining_to_spawn())
+            });
+        }
+        if let Some(speed) = speed {
+            set_text(&ui_finder, &mut ui_texts, SPEED_TEXT, || speed.to_string());
+        }
+        // So that whoever runs into something odd can say which run it was
+        if let Some(rng) = rng {
+            set_text(&ui_finder, &mut ui_texts, SEED_TEXT, || {
+                format!("Seed: {}", rng.seed())
+            });
+        }
+    }
+}
+
+// The HUD might not have finished loading yet, in which case there's nothing to update
+fn set_text<F: FnOnce() -> String>(
+    ui_finder: &UiFinder,
+    ui_texts: &mut WriteStorage<UiText>,
+    id: &str,
+    text: F,
+) {
+    if let Some(ui_text) = ui_finder.find(id).and_then(|e| ui_texts.get_mut(e)) {
+        let text = text();
+        if ui_text.text != text {
+            ui_text.text = text;
+        }
+    }
+}
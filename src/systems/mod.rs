@@ -2,14 +2,22 @@ mod bullet;
 mod camera;
 mod controls;
 mod enemies;
+mod health_bar;
+mod hud;
 mod physics;
+mod reload;
+mod restore;
 mod ui;
 
 pub use self::{
     bullet::BulletSystem,
     camera::CameraFollowSystem,
     controls::{PlayerControlSystem, ShooterControlSystem, TowerDirectionSystem},
-    enemies::{EnemyMovementSystem, EnemySpawnSystem},
+    enemies::{EnemyMovementSystem, EnemySpawnSystem, PathfindingSystem},
+    health_bar::HealthBarSystem,
+    hud::HudSystem,
     physics::PhysicsSystem,
+    reload::{apply_reloads, ConfigReloadSystem},
+    restore::RestoreSystem,
     ui::{PlacementSystem, UiEventHandlerSystem, UiEventHandlerSystemDesc},
 };
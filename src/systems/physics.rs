@@ -1,6 +1,6 @@
-use crate::components::Velocity;
+use crate::{components::Velocity, resources::SimulationClock};
 use amethyst::{
-    core::{Time, Transform},
+    core::Transform,
     derive::SystemDesc,
     ecs::{prelude::*, ReadStorage, System, WriteStorage},
 };
@@ -12,14 +12,13 @@ impl<'s> System<'s> for PhysicsSystem {
     type SystemData = (
         ReadStorage<'s, Velocity>,
         WriteStorage<'s, Transform>,
-        Read<'s, Time>,
+        Read<'s, SimulationClock>,
     );
 
-    fn run(&mut self, (velocities, mut transforms, time): Self::SystemData) {
-        let frame_delta_s = time.fixed_time().as_secs_f32();
+    fn run(&mut self, (velocities, mut transforms, clock): Self::SystemData) {
         for (velocity, transform) in (&velocities, &mut transforms).join() {
             let position = transform.translation_mut();
-            let diff = velocity.v * frame_delta_s;
+            let diff = velocity.v * clock.step;
             position.x += diff.x;
             position.y += diff.y;
         }
@@ -0,0 +1,237 @@
+use crate::resources::{
+    self, BulletPrefabSet, EndlessWaves, EnemyPrefabSet, GameplayConfig, LevelMap, TowerPrefabSet,
+    WallPrefabSet, WaveSchedule,
+};
+use amethyst::{
+    assets::ProgressCounter,
+    derive::SystemDesc,
+    ecs::{prelude::*, System},
+    utils::application_root_dir,
+};
+use log::{error, info};
+use std::{
+    collections::HashMap,
+    fs, mem,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+// How often the files are checked for changes
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Changes picked up from the files, waiting for the simulation to put them in between two ticks.
+/// Only around with --hot-reload.
+#[derive(Default)]
+pub struct PendingReload {
+    gameplay: Option<GameplayConfig>,
+    // Along with the level they're from. They're lined up with how far the run has got once they
+    // go in
+    waves: Option<(String, WaveSchedule)>,
+    prefabs: bool,
+    // New gameplay prefab sets, which replace the old ones once they're done loading
+    loading: Option<(ProgressCounter, PrefabSets)>,
+}
+
+type PrefabSets = (
+    EnemyPrefabSet,
+    TowerPrefabSet,
+    BulletPrefabSet,
+    WallPrefabSet,
+);
+
+// Picks up changes to the gameplay config, the current level's waves and the prefabs while the
+// game is running. Files that fail to load are logged and the old values are kept. Nothing
+// changes here, see `apply_reloads`
+#[derive(Default, SystemDesc)]
+pub struct ConfigReloadSystem {
+    last_check: Option<Instant>,
+    // When each file was last modified, as of the last check
+    modified: HashMap<PathBuf, SystemTime>,
+}
+
+impl<'s> System<'s> for ConfigReloadSystem {
+    type SystemData = (Write<'s, PendingReload>, Option<Read<'s, LevelMap>>);
+
+    fn run(&mut self, (mut pending, level_map): Self::SystemData) {
+        if self
+            .last_check
+            .is_some_and(|last| last.elapsed() < CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.last_check = Some(Instant::now());
+        let root = match application_root_dir() {
+            Ok(r) => r,
+            Err(_) => return,
+        };
+
+        let gameplay_path = root.join("config").join("gameplay.ron");
+        if self.changed(&gameplay_path) {
+            match resources::load_gameplay_config(&gameplay_path) {
+                Ok(config) => pending.gameplay = Some(config),
+                Err(e) => error!("{}", e),
+            }
+        }
+
+        if let Some(level_map) = level_map {
+            let level_path = root
+                .join("assets")
+                .join("levels")
+                .join(format!("{}.ron", level_map.name()));
+            if self.changed(&level_path) {
+                match resources::load_level(&level_path) {
+                    Ok(level) => {
+                        pending.waves = Some((level_map.name().to_string(), level.waves));
+                    }
+                    Err(e) => error!("Failed to reload waves: {}", e),
+                }
+            }
+        }
+
+        // Whichever prefab changed, they all get loaded again
+        let prefab_paths: Vec<PathBuf> = match fs::read_dir(root.join("assets").join("prefabs")) {
+            Ok(dir) => dir
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|e| e == "ron"))
+                .collect(),
+            Err(_) => vec![],
+        };
+        for path in prefab_paths {
+            if self.changed(&path) {
+                pending.prefabs = true;
+            }
+        }
+    }
+}
+
+impl ConfigReloadSystem {
+    // Whether the file changed since the last check. The first time a file is checked only takes
+    // note of it
+    fn changed(&mut self, path: &Path) -> bool {
+        let modified = match fs::metadata(path).and_then(|m| m.modified()) {
+            Ok(m) => m,
+            Err(_) => return false,
+        };
+        self.modified
+            .insert(path.to_path_buf(), modified)
+            .is_some_and(|previous| previous != modified)
+    }
+}
+
+/// Puts whatever was reloaded into the world. Only ever called between two ticks, so that no
+/// tick sees half of a reload.
+pub fn apply_reloads(world: &mut World) {
+    let (gameplay, waves, prefabs) = match world.try_fetch_mut::<PendingReload>() {
+        Some(mut pending) => (
+            pending.gameplay.take(),
+            pending.waves.take(),
+            mem::take(&mut pending.prefabs),
+        ),
+        None => return,
+    };
+
+    if let Some(gameplay) = gameplay {
+        info!("Reloaded the gameplay config");
+        world.insert(gameplay);
+    }
+
+    // Waves picked up during a run that's over since have nowhere to go
+    let waves = waves.filter(|(level, _)| {
+        world
+            .try_fetch::<LevelMap>()
+            .is_some_and(|level_map| level_map.name() == level)
+    });
+    if let Some((_, mut waves)) = waves {
+        // The schedule of an endless run goes on past the level's own waves
+        if world.has_value::<EndlessWaves>() {
+            info!("Endless runs keep their waves, start a new run to play the reloaded ones");
+        } else if let Some(mut wave_schedule) = world.try_fetch_mut::<WaveSchedule>() {
+            // The new waves pick up where the current ones are at, so they have to line up with
+            // how far the run has got
+            match waves.restore(wave_schedule.progress()) {
+                Ok(()) => {
+                    info!("Reloaded the waves");
+                    *wave_schedule = waves;
+                }
+                Err(e) => error!("Failed to reload waves: {}", e),
+            }
+        }
+    }
+
+    if prefabs {
+        let mut progress_counter = ProgressCounter::new();
+        let sets = (
+            EnemyPrefabSet::new(world, &mut progress_counter),
+            TowerPrefabSet::new(world, &mut progress_counter),
+            BulletPrefabSet::new(world, &mut progress_counter),
+            WallPrefabSet::new(world, &mut progress_counter),
+        );
+        world.write_resource::<PendingReload>().loading = Some((progress_counter, sets));
+    }
+
+    // Swapped in all at once, so nothing gets built from a prefab that's still loading
+    let loaded = {
+        let mut pending = world.write_resource::<PendingReload>();
+        match pending.loading {
+            Some((ref progress_counter, _)) if !progress_counter.errors().is_empty() => {
+                error!("Failed to reload prefabs, keeping the old ones");
+                pending.loading = None;
+                None
+            }
+            Some((ref progress_counter, _)) if progress_counter.is_complete() => {
+                pending.loading.take().map(|(_, sets)| sets)
+            }
+            _ => None,
+        }
+    };
+    if let Some((enemies, towers, bullets, walls)) = loaded {
+        info!("Reloaded the prefabs");
+        world.insert(enemies);
+        world.insert(towers);
+        world.insert(bullets);
+        world.insert(walls);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Harness;
+
+    fn reload_waves(harness: &mut Harness) {
+        let level = resources::load_level(
+            application_root_dir()
+                .unwrap()
+                .join("assets")
+                .join("levels")
+                .join("level1.ron"),
+        )
+        .unwrap();
+        harness.world.insert(PendingReload {
+            waves: Some(("level1".to_string(), level.waves)),
+            ..Default::default()
+        });
+        apply_reloads(&mut harness.world);
+    }
+
+    #[test]
+    fn waves_are_reloaded_between_ticks() {
+        let mut harness = Harness::new("level1");
+        reload_waves(&mut harness);
+        assert!(harness.world.read_resource::<WaveSchedule>().total_waves() > 0);
+    }
+
+    #[test]
+    fn endless_runs_keep_their_waves() {
+        let mut harness = Harness::new("level1");
+        let schedule = WaveSchedule::new(vec![]);
+        harness.world.insert(EndlessWaves::new(1, &schedule));
+        harness.world.insert(schedule);
+
+        reload_waves(&mut harness);
+        assert_eq!(
+            harness.world.read_resource::<WaveSchedule>().total_waves(),
+            0
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{
+    components::{ControlledCharacter, Hitable, Restore, Tower},
+    resources::FollowedObject,
+};
+use amethyst::{
+    core::Transform,
+    derive::SystemDesc,
+    ecs::{Entities, Join, System, SystemData, Write, WriteStorage},
+};
+
+// Puts saved values back on restored entities. It has to run right after the prefab loaders, so
+// that they're in place before any of the gameplay systems get to them
+#[derive(SystemDesc)]
+pub struct RestoreSystem;
+
+impl<'s> System<'s> for RestoreSystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, Restore>,
+        WriteStorage<'s, Hitable>,
+        WriteStorage<'s, Tower>,
+        WriteStorage<'s, ControlledCharacter>,
+        WriteStorage<'s, Transform>,
+        Option<Write<'s, FollowedObject>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut restores,
+            mut hitables,
+            mut towers,
+            mut controlled_characters,
+            mut transforms,
+            mut followed_object,
+        ): Self::SystemData,
+    ) {
+        let mut restored = vec![];
+        for (entity, restore) in (&entities, &restores).join() {
+            // Each of these is only there once the entity's prefab has been applied
+            match restore {
+                Restore::Enemy { health, max_health } => {
+                    if let Some(hitable) = hitables.get_mut(entity) {
+                        hitable.health = *health;
+                        if let Some(max_health) = max_health {
+                            hitable.max_health = *max_health;
+                        }
+                        restored.push(entity);
+                    }
+                }
+                Restore::Toughened { factor } => {
+                    if let Some(hitable) = hitables.get_mut(entity) {
+                        hitable.health *= factor;
+                        hitable.max_health *= factor;
+                        restored.push(entity);
+                    }
+                }
+                Restore::Tower { active } => {
+                    if let Some(tower) = towers.get_mut(entity) {
+                        tower.active = *active;
+                        restored.push(entity);
+                    }
+                }
+                Restore::Player {
+                    translation,
+                    locked,
+                } => {
+                    if let Some(controlled_character) = controlled_characters.get_mut(entity) {
+                        controlled_character.locked = *locked;
+                        if let Some(transform) = transforms.get_mut(entity) {
+                            transform.set_translation(*translation);
+                        }
+                        // Players inside a tower keep the camera locked on them
+                        if let Some(ref mut followed_object) = followed_object {
+                            if followed_object.e == entity {
+                                followed_object.hard_lock = *locked;
+                            }
+                        }
+                        restored.push(entity);
+                    }
+                }
+            }
+        }
+
+        for entity in restored {
+            restores.remove(entity);
+        }
+    }
+}
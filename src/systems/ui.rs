@@ -1,21 +1,27 @@
 use crate::{
-    input::{self, ActionBinding, GameBindingTypes},
-    prefabs::TowerPrefab,
-    resources::{TowerPlacement, TowerPrefabSet, TowerType},
+    components::{Destination, TowerKind, Wall},
+    input::{PendingSelection, PendingWaveCall, TickInput},
+    pathfinding::Cell,
+    prefabs::{TowerPrefab, WallPrefab},
+    resources::{
+        Buildable, Economy, GameplayConfig, LevelMap, RunStats, TowerPlacement, TowerPrefabSet,
+        TowerType, WallPrefabSet,
+    },
 };
 use amethyst::{
     assets::{Handle, Prefab},
     core::Transform,
     derive::SystemDesc,
-    ecs::{Entities, Read, ReadExpect, ReadStorage, System, SystemData, Write, WriteStorage},
-    input::InputHandler,
-    renderer::{ActiveCamera, Camera},
+    ecs::{Entities, Join, Read, ReadStorage, System, SystemData, Write, WriteStorage},
     shrev::{EventChannel, ReaderId},
     ui::{UiEvent, UiEventType, UiFinder},
-    window::ScreenDimensions,
 };
+use log::warn;
+use nalgebra::Point2;
 
 const BUTTON_TOWER: &str = "button";
+const BUTTON_WALL: &str = "wall_button";
+const BUTTON_NEXT_WAVE: &str = "next_wave_button";
 
 #[derive(SystemDesc)]
 #[system_desc(name(UiEventHandlerSystemDesc))]
@@ -34,27 +40,33 @@ impl<'s> System<'s> for UiEventHandlerSystem {
     type SystemData = (
         Write<'s, EventChannel<UiEvent>>,
         UiFinder<'s>,
-        Option<Write<'s, TowerPlacement>>,
+        Option<Write<'s, PendingSelection>>,
+        Option<Write<'s, PendingWaveCall>>,
     );
 
-    fn run(&mut self, (events, ui_finder, tower_placement): Self::SystemData) {
-        match tower_placement {
-            Some(mut tower_placement) => {
+    // Clicks are only handed over to the simulation on its next tick, see `TickInput`
+    fn run(&mut self, (events, ui_finder, pending_selection, pending_wave_call): Self::SystemData) {
+        match (pending_selection, pending_wave_call) {
+            (Some(mut pending_selection), Some(mut pending_wave_call)) => {
                 for ev in events.read(&mut self.reader_id) {
-                    // Look for tower clicks
+                    // Look for build button clicks
                     if ev.event_type == UiEventType::Click {
-                        let button_entity = ui_finder.find(BUTTON_TOWER);
-
-                        // If the entity pressed is the tower button, start placing
-                        if let Some(button_entity) = button_entity {
-                            if button_entity == ev.target {
-                                tower_placement.placing = true;
+                        // If the entity pressed is one of the build buttons, start placing
+                        for (button_id, buildable) in &[
+                            (BUTTON_TOWER, Buildable::Tower(TowerType::Standard)),
+                            (BUTTON_WALL, Buildable::Wall),
+                        ] {
+                            if ui_finder.find(button_id) == Some(ev.target) {
+                                pending_selection.0 = Some(*buildable);
                             }
                         }
+                        if ui_finder.find(BUTTON_NEXT_WAVE) == Some(ev.target) {
+                            pending_wave_call.0 = true;
+                        }
                     }
                 }
             }
-            None => {
+            _ => {
                 // Read until emptied out. We want to ignore old events
                 for _ in events.read(&mut self.reader_id) {}
             }
@@ -70,65 +82,240 @@ pub struct PlacementSystem {
 impl<'s> System<'s> for PlacementSystem {
     type SystemData = (
         Entities<'s>,
-        ReadStorage<'s, Camera>,
+        ReadStorage<'s, Destination>,
         WriteStorage<'s, Transform>,
+        WriteStorage<'s, Wall>,
+        WriteStorage<'s, TowerKind>,
         WriteStorage<'s, Handle<Prefab<TowerPrefab>>>,
+        WriteStorage<'s, Handle<Prefab<WallPrefab>>>,
         Option<Write<'s, TowerPlacement>>,
+        Option<Write<'s, LevelMap>>,
+        Option<Write<'s, Economy>>,
+        Option<Write<'s, RunStats>>,
         Read<'s, TowerPrefabSet>,
-        Read<'s, InputHandler<GameBindingTypes>>,
-        Read<'s, ActiveCamera>,
-        ReadExpect<'s, ScreenDimensions>,
+        Read<'s, WallPrefabSet>,
+        Read<'s, GameplayConfig>,
+        Read<'s, TickInput>,
     );
 
     fn run(
         &mut self,
         (
             entities,
-            cameras,
+            destinations,
             mut transforms,
+            mut walls,
+            mut tower_kinds,
             mut tower_prefabs,
+            mut wall_prefabs,
             mut tower_placement,
+            mut level_map,
+            mut economy,
+            mut run_stats,
             tower_prefab_set,
-            input_handler,
-            active_camera,
-            screen_dimensions,
+            wall_prefab_set,
+            gameplay,
+            input,
         ): Self::SystemData,
     ) {
         if let Some(ref mut tower_placement) = tower_placement {
+            if let Some(buildable) = input.select {
+                tower_placement.placing = Some(buildable);
+            }
             // Do not track button presses unless we're placing
-            if tower_placement.placing {
-                let place_is_pressed = input_handler
-                    .action_is_down(&ActionBinding::Place)
-                    .unwrap_or(false);
-                if !place_is_pressed && self.place_was_pressed {
-                    let position = match input::get_mouse_projection(
-                        &entities,
-                        &input_handler,
-                        &transforms,
-                        &cameras,
-                        &active_camera,
-                        &screen_dimensions,
-                    ) {
+            if let Some(buildable) = tower_placement.placing {
+                // Kept up to date before anything can bail out, or a failed placement would be
+                // retried on every tick after
+                let released = !input.place && self.place_was_pressed;
+                self.place_was_pressed = input.place;
+                if released {
+                    let position = match input.mouse {
                         Some(p) => p,
                         None => {
                             return;
                         }
                     };
 
-                    tower_prefab_set
-                        .add_tower(
-                            TowerType::Standard,
-                            position,
-                            &entities,
-                            &mut tower_prefabs,
-                            &mut transforms,
-                        )
-                        .expect("Failed to add tower");
-                    tower_placement.placing = false;
+                    match buildable {
+                        Buildable::Tower(tower_type) => {
+                            if !Self::pay(&mut economy, buildable, &gameplay) {
+                                return;
+                            }
+                            tower_prefab_set
+                                .add_tower(
+                                    tower_type,
+                                    position,
+                                    &entities,
+                                    &mut tower_prefabs,
+                                    &mut transforms,
+                                    &mut tower_kinds,
+                                )
+                                .expect("Failed to add tower");
+                            if let Some(ref mut run_stats) = run_stats {
+                                run_stats.towers_built += 1;
+                            }
+                            tower_placement.placing = None;
+                        }
+                        Buildable::Wall => {
+                            let level_map = match level_map {
+                                Some(ref mut l) => l,
+                                None => {
+                                    return;
+                                }
+                            };
+                            // Rejected placements keep us in placing mode so another cell can
+                            // be picked
+                            let cell =
+                                self.wall_cell(position, level_map, &destinations, &transforms);
+                            if let Some(cell) =
+                                cell.filter(|_| Self::pay(&mut economy, buildable, &gameplay))
+                            {
+                                level_map.set_walkable(cell, false);
+                                wall_prefab_set
+                                    .add_wall(
+                                        level_map.cell_to_world(cell),
+                                        &entities,
+                                        &mut wall_prefabs,
+                                        &mut walls,
+                                        &mut transforms,
+                                    )
+                                    .expect("Failed to add wall");
+                                tower_placement.placing = None;
+                            }
+                        }
+                    }
                 }
+            }
+        }
+    }
+}
 
-                self.place_was_pressed = place_is_pressed;
+impl PlacementSystem {
+    // Takes the cost of the buildable out of the player's money, if they can afford it. Without an
+    // economy everything is free
+    fn pay(
+        economy: &mut Option<Write<'_, Economy>>,
+        buildable: Buildable,
+        gameplay: &GameplayConfig,
+    ) -> bool {
+        match economy {
+            Some(economy) => {
+                let paid = economy.spend(buildable.cost(gameplay));
+                if !paid {
+                    warn!("Not enough money to build {:?}", buildable);
+                }
+                paid
             }
+            None => true,
         }
     }
+
+    // Returns the cell a wall would go in if placed at the given position, as long as blocking it
+    // still leaves both the spawn and every enemy on the map a way to the exit
+    fn wall_cell<'s>(
+        &self,
+        position: Point2<f32>,
+        level_map: &LevelMap,
+        destinations: &ReadStorage<'s, Destination>,
+        transforms: &WriteStorage<'s, Transform>,
+    ) -> Option<Cell> {
+        let cell = level_map.cell_at(position)?;
+        let occupied: Vec<_> = (destinations, transforms)
+            .join()
+            .map(|(destination, transform)| {
+                let position = Point2::new(transform.translation().x, transform.translation().y);
+                (level_map.world_to_cell(position), destination.exit)
+            })
+            .collect();
+
+        if level_map.can_block(cell, &occupied) {
+            Some(cell)
+        } else {
+            warn!("Can't place a wall on {:?}, it would block the path", cell);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::{TowerKind, Wall},
+        harness::Harness,
+        input::TickInput,
+        resources::{Buildable, Economy, GameplayConfig, LevelMap, TowerType},
+    };
+    use amethyst::ecs::{Join, WorldExt};
+
+    #[test]
+    fn placing_a_tower_costs_money() {
+        let mut harness = Harness::new("level1");
+        let money = harness.world.read_resource::<Economy>().money;
+        let aim = TickInput {
+            mouse: Some(harness.cell((10, 8))),
+            ..Default::default()
+        };
+        harness.step(TickInput {
+            select: Some(Buildable::Tower(TowerType::Standard)),
+            ..aim.clone()
+        });
+
+        harness.tap(&aim, |input| input.place = true);
+
+        assert_eq!(harness.world.read_storage::<TowerKind>().join().count(), 1);
+        let cost = Buildable::Tower(TowerType::Standard)
+            .cost(&harness.world.read_resource::<GameplayConfig>());
+        assert_eq!(harness.world.read_resource::<Economy>().money, money - cost);
+    }
+
+    #[test]
+    fn towers_are_not_placed_without_the_money() {
+        let mut harness = Harness::new("level1");
+        harness.world.insert(Economy { money: 0 });
+        let aim = TickInput {
+            mouse: Some(harness.cell((10, 8))),
+            ..Default::default()
+        };
+        harness.step(TickInput {
+            select: Some(Buildable::Tower(TowerType::Standard)),
+            ..aim.clone()
+        });
+
+        harness.tap(&aim, |input| input.place = true);
+        // Money coming in later doesn't place it on its own
+        harness.world.insert(Economy { money: 1000 });
+        harness.run(10, &aim);
+
+        assert_eq!(harness.world.read_storage::<TowerKind>().join().count(), 0);
+        assert_eq!(harness.world.read_resource::<Economy>().money, 1000);
+    }
+
+    #[test]
+    fn walls_cannot_block_the_path() {
+        let mut harness = Harness::new("level1");
+        let money = harness.world.read_resource::<Economy>().money;
+        // The south exit is at the end of a corridor three cells wide
+        for x in 26..29 {
+            let aim = TickInput {
+                mouse: Some(harness.cell((x, 2))),
+                ..Default::default()
+            };
+            harness.step(TickInput {
+                select: Some(Buildable::Wall),
+                ..aim.clone()
+            });
+            harness.tap(&aim, |input| input.place = true);
+        }
+
+        assert_eq!(harness.world.read_storage::<Wall>().join().count(), 2);
+        assert!(harness
+            .world
+            .read_resource::<LevelMap>()
+            .is_walkable((28, 2)));
+        let cost = Buildable::Wall.cost(&harness.world.read_resource::<GameplayConfig>());
+        assert_eq!(
+            harness.world.read_resource::<Economy>().money,
+            money - 2 * cost
+        );
+    }
 }
@@ -0,0 +1,297 @@
+use crate::{
+    audio,
+    prefabs::{
+        BackgroundPrefab, BulletPrefab, EnemyPrefab, HealthBarPrefab, PlayerPrefab,
+        SplashAnimationPrefab, TowerPrefab, WallPrefab,
+    },
+    resources::{self, BulletPrefabSet, EnemyPrefabSet, TowerPrefabSet, WallPrefabSet},
+};
+use amethyst::{assets::Prefab, ui::UiWidget, utils::application_root_dir};
+use serde::de::DeserializeOwned;
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+// Checks that a file's contents parse as the type the game loads it as
+type Parse = fn(&str) -> Result<(), ron::de::Error>;
+
+// Every prefab the game loads outside of the prefab sets, along with how to parse it. The sets'
+// own are listed by `prefabs`
+const PREFABS: &[(&str, Parse)] = &[
+    ("prefabs/background.ron", parses::<Prefab<BackgroundPrefab>>),
+    ("prefabs/health_bar.ron", parses::<Prefab<HealthBarPrefab>>),
+    ("prefabs/player.ron", parses::<Prefab<PlayerPrefab>>),
+    (
+        "prefabs/splash.ron",
+        parses::<Prefab<SplashAnimationPrefab>>,
+    ),
+];
+
+// Every prefab the game loads, along with how to parse it. The gameplay ones come from the
+// prefab sets, so that new ones can't be left out
+fn prefabs() -> Vec<(&'static str, Parse)> {
+    fn parsed_as<T: DeserializeOwned>(
+        paths: impl Iterator<Item = &'static str>,
+    ) -> impl Iterator<Item = (&'static str, Parse)> {
+        paths.map(|path| (path, parses::<T> as Parse))
+    }
+
+    PREFABS
+        .iter()
+        .copied()
+        .chain(parsed_as::<Prefab<EnemyPrefab>>(
+            EnemyPrefabSet::prefab_paths(),
+        ))
+        .chain(parsed_as::<Prefab<BulletPrefab>>(
+            BulletPrefabSet::prefab_paths(),
+        ))
+        .chain(parsed_as::<Prefab<TowerPrefab>>(
+            TowerPrefabSet::prefab_paths(),
+        ))
+        .chain(parsed_as::<Prefab<WallPrefab>>(
+            WallPrefabSet::prefab_paths(),
+        ))
+        .collect()
+}
+
+// A problem with one of the files, as precisely located as the check allows
+struct Diagnostic {
+    path: PathBuf,
+    line: Option<usize>,
+    message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+/// Checks every prefab, UI file, level and config the game loads, without starting it, and
+/// prints whatever is wrong with them. Fails if anything is.
+pub fn run() -> anyhow::Result<()> {
+    let root = application_root_dir()?;
+    let assets = root.join("assets");
+    let mut diagnostics = vec![];
+    // Problems the game copes with, which don't fail the check
+    let mut warnings = vec![];
+
+    for (path, parse) in prefabs() {
+        check_ron_file(&assets, &assets.join(path), parse, &mut diagnostics);
+    }
+    for path in ron_files(&assets.join("ui"))? {
+        check_ron_file(&assets, &path, parses::<UiWidget>, &mut diagnostics);
+    }
+    for track in &[audio::MUSIC_TRACK, audio::INTRO_BUZZ_TRACK] {
+        if !assets.join(track).is_file() {
+            warnings.push(Diagnostic {
+                path: assets.join(track),
+                line: None,
+                message: "Audio track is missing, the game will go without it".to_string(),
+            });
+        }
+    }
+    for path in ron_files(&assets.join("levels"))? {
+        check_level(&path, &mut diagnostics);
+    }
+    let gameplay_path = root.join("config").join("gameplay.ron");
+    if let Err(e) = resources::load_gameplay_config(&gameplay_path) {
+        diagnostics.push(Diagnostic {
+            path: gameplay_path,
+            line: None,
+            message: e.to_string(),
+        });
+    }
+
+    let problems = diagnostics.len();
+    let relative = |mut diagnostic: Diagnostic| {
+        if let Ok(path) = diagnostic.path.strip_prefix(&root) {
+            diagnostic.path = path.to_path_buf();
+        }
+        diagnostic
+    };
+    for warning in warnings {
+        println!("warning: {}", relative(warning));
+    }
+    for diagnostic in diagnostics {
+        println!("{}", relative(diagnostic));
+    }
+    anyhow::ensure!(
+        problems == 0,
+        "Found {} problem(s) with the assets",
+        problems
+    );
+    println!("All assets are fine");
+    Ok(())
+}
+
+fn parses<T: DeserializeOwned>(text: &str) -> Result<(), ron::de::Error> {
+    ron::de::from_str::<T>(text).map(|_| ())
+}
+
+fn ron_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in
+        fs::read_dir(dir).map_err(|e| anyhow::anyhow!("Failed to read {:?}: {}", dir, e))?
+    {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "ron") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// Parses the file, and makes sure every file it refers to is there
+fn check_ron_file(assets: &Path, path: &Path, parse: Parse, diagnostics: &mut Vec<Diagnostic>) {
+    let text = match fs::read_to_string(path) {
+        Ok(t) => t,
+        Err(e) => {
+            diagnostics.push(Diagnostic {
+                path: path.to_path_buf(),
+                line: None,
+                message: format!("Failed to read: {}", e),
+            });
+            return;
+        }
+    };
+
+    if let Err(e) = parse(&text) {
+        // Only syntax errors come with a position. Errors from serde, like a missing field, don't
+        let (line, message) = match e {
+            ron::de::Error::Message(ref message) => {
+                (unknown_name_line(&text, message), e.to_string())
+            }
+            ron::de::Error::Parser(_, position) => {
+                let prefix = format!("{}: ", position);
+                let message = e.to_string();
+                let message = message.strip_prefix(&prefix).unwrap_or(&message);
+                (Some(position.line), message.to_string())
+            }
+            _ => (None, e.to_string()),
+        };
+        diagnostics.push(Diagnostic {
+            path: path.to_path_buf(),
+            line,
+            message,
+        });
+    }
+
+    for (line, file) in referenced_files(&text) {
+        if !assets.join(file).is_file() {
+            diagnostics.push(Diagnostic {
+                path: path.to_path_buf(),
+                line: Some(line),
+                message: format!("Refers to {:?}, which doesn't exist", file),
+            });
+        }
+    }
+}
+
+// Serde doesn't say where an unknown field or variant is, but as long as the name is only used
+// once it's easy enough to find
+fn unknown_name_line(text: &str, message: &str) -> Option<usize> {
+    let name = message
+        .strip_prefix("unknown field `")
+        .or_else(|| message.strip_prefix("unknown variant `"))?;
+    let name = &name[..name.find('`')?];
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.contains(name));
+    match (lines.next(), lines.next()) {
+        (Some((index, _)), None) => Some(index + 1),
+        _ => None,
+    }
+}
+
+// Files referred to with `File("...")`, like textures and fonts, along with the line they're on
+fn referenced_files(text: &str) -> Vec<(usize, &str)> {
+    const START: &str = "File(\"";
+    text.lines()
+        .enumerate()
+        .flat_map(|(index, line)| {
+            line.match_indices(START).filter_map(move |(start, _)| {
+                let rest = &line[start + START.len()..];
+                rest.find('"').map(|end| (index + 1, &rest[..end]))
+            })
+        })
+        .collect()
+}
+
+// Loading the level checks its map and waves. On top of that, enemies need a path from each
+// spawn to each exit the waves send them along, or they'd have no waypoints to follow
+fn check_level(path: &Path, diagnostics: &mut Vec<Diagnostic>) {
+    let level = match resources::load_level(path) {
+        Ok(l) => l,
+        Err(e) => {
+            diagnostics.push(Diagnostic {
+                path: path.to_path_buf(),
+                line: None,
+                message: e.to_string(),
+            });
+            return;
+        }
+    };
+
+    let mut routes: Vec<(&str, &str)> = level
+        .waves
+        .waves()
+        .iter()
+        .flat_map(|wave| wave.groups.iter())
+        .map(|group| (group.spawn.as_str(), group.exit.as_str()))
+        .collect();
+    routes.sort_unstable();
+    routes.dedup();
+    for (spawn, exit) in routes {
+        let (spawn_cell, exit_cell) = match (level.map.spawn(spawn), level.map.exit(exit)) {
+            (Some(s), Some(e)) => (s, e),
+            _ => continue,
+        };
+        let path_found = level
+            .map
+            .path_to(level.map.cell_to_world(spawn_cell), exit_cell)
+            .is_some_and(|waypoints| !waypoints.is_empty());
+        if !path_found {
+            diagnostics.push(Diagnostic {
+                path: path.to_path_buf(),
+                line: None,
+                message: format!("There's no path from spawn {:?} to exit {:?}", spawn, exit),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Audio is left out, as the music isn't checked in
+    #[test]
+    fn prefabs_and_ui_are_valid() {
+        let assets = application_root_dir().unwrap().join("assets");
+        let mut diagnostics = vec![];
+        for (path, parse) in prefabs() {
+            check_ron_file(&assets, &assets.join(path), parse, &mut diagnostics);
+        }
+        for path in ron_files(&assets.join("ui")).unwrap() {
+            check_ron_file(&assets, &path, parses::<UiWidget>, &mut diagnostics);
+        }
+        let problems: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+        assert!(problems.is_empty(), "{:#?}", problems);
+    }
+
+    #[test]
+    fn finds_referenced_files() {
+        let text = "(\n    font: File(\"fonts/a.ttf\", (\"TTF\", ())),\n    id: \"File\",\n    texture: File(\"sprites/b.png\", \"IMAGE\"),\n)";
+        assert_eq!(
+            referenced_files(text),
+            vec![(2, "fonts/a.ttf"), (4, "sprites/b.png")]
+        );
+    }
+}